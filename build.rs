@@ -130,6 +130,10 @@ fn build(build_type: &str) {
         .allowlist_type("(av|AV).*")
         .allowlist_function("(av|AV).*")
         .allowlist_var("(av|AV).*")
+        .allowlist_type("(Sws|Swr).*")
+        .allowlist_function("(sws|swr)_.*")
+        .allowlist_var("(SWS|SWR)_.*")
+        .allowlist_var("FF_.*")
         .generate()
         .expect("Unable to generate");
 
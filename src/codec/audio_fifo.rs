@@ -0,0 +1,160 @@
+use crate::ff_error::*;
+use crate::*;
+use libc::{c_int, c_void, EINVAL, ENOMEM};
+use log::error;
+
+/// buffers audio samples so that an encoder with a fixed frame_size can be fed
+/// frames of any length, the emitted frames are timestamped by sample count
+pub(crate) struct AudioFifo {
+    fifo: *mut AVAudioFifo,
+    frame: *mut AVFrame,
+    frame_size: c_int,
+    next_pts: Option<i64>,
+}
+
+impl Drop for AudioFifo {
+    fn drop(&mut self) {
+        unsafe {
+            if !self.fifo.is_null() {
+                av_audio_fifo_free(self.fifo);
+            }
+            av_frame_free(&mut self.frame);
+        }
+    }
+}
+
+impl AudioFifo {
+    pub fn new(codec_ctx: *const AVCodecContext) -> Result<Self> {
+        unsafe {
+            let ctx = &*codec_ctx;
+            let fifo = av_audio_fifo_alloc(ctx.sample_fmt, ctx.channels, ctx.frame_size);
+            if fifo.is_null() {
+                return Err(Error::Io(ENOMEM));
+            }
+
+            let frame = av_frame_alloc();
+            if frame.is_null() {
+                av_audio_fifo_free(fifo);
+                return Err(Error::Io(ENOMEM));
+            }
+
+            Ok(AudioFifo {
+                fifo,
+                frame,
+                frame_size: ctx.frame_size,
+                next_pts: None,
+            })
+        }
+    }
+
+    pub fn write(&mut self, codec_ctx: *const AVCodecContext, frame: &AVFrame) -> Result<()> {
+        unsafe {
+            let ctx = &*codec_ctx;
+            if frame.format != ctx.sample_fmt || frame.channels != ctx.channels {
+                error!(
+                    "audio frame(format:{}, channels:{}) doesn't match the encoder(format:{}, channels:{})",
+                    frame.format, frame.channels, ctx.sample_fmt, ctx.channels
+                );
+                return Err(Error::Io(EINVAL));
+            }
+
+            if self.next_pts.is_none() {
+                self.next_pts = Some(if frame.pts != AV_NOPTS_VALUE {
+                    frame.pts
+                } else {
+                    0
+                });
+            }
+
+            let ret = av_audio_fifo_write(
+                self.fifo,
+                frame.extended_data as *mut *mut c_void,
+                frame.nb_samples,
+            );
+            check(ret).map(|_| ())
+        }
+    }
+
+    /// send as many full frames as are buffered to the encoder, or whatever is
+    /// left if `flush` is true. the last frame is padded with silence up to
+    /// frame_size unless the encoder accepts a smaller one. samples are kept in
+    /// the fifo if the encoder fails with Error::Again, they will be resent by
+    /// the next call.
+    pub fn send_to(&mut self, codec_ctx: *mut AVCodecContext, flush: bool) -> Result<()> {
+        unsafe {
+            loop {
+                let buffered = av_audio_fifo_size(self.fifo);
+                if buffered == 0 || (buffered < self.frame_size && !flush) {
+                    return Ok(());
+                }
+
+                let nb_samples = buffered.min(self.frame_size);
+                let small_last_frame = !(*codec_ctx).codec.is_null()
+                    && (*(*codec_ctx).codec).capabilities & AV_CODEC_CAP_SMALL_LAST_FRAME as c_int
+                        != 0;
+                let frame_samples = if small_last_frame {
+                    nb_samples
+                } else {
+                    self.frame_size
+                };
+                self.fill_frame(codec_ctx, nb_samples, frame_samples)?;
+
+                let ret = avcodec_send_frame(codec_ctx, self.frame);
+                av_frame_unref(self.frame);
+                check(ret)?;
+
+                av_audio_fifo_drain(self.fifo, nb_samples);
+                let ctx = &*codec_ctx;
+                let duration = av_rescale_q(
+                    frame_samples as i64,
+                    AVRational {
+                        num: 1,
+                        den: ctx.sample_rate,
+                    },
+                    ctx.time_base,
+                );
+                self.next_pts = self.next_pts.map(|pts| pts + duration);
+            }
+        }
+    }
+
+    // a frame of frame_samples samples, the first nb_samples of which are
+    // peeked from the fifo and the rest silence
+    unsafe fn fill_frame(
+        &mut self,
+        codec_ctx: *const AVCodecContext,
+        nb_samples: c_int,
+        frame_samples: c_int,
+    ) -> Result<()> {
+        let ctx = &*codec_ctx;
+        let frame = &mut *self.frame;
+        frame.nb_samples = frame_samples;
+        frame.format = ctx.sample_fmt;
+        frame.channel_layout = ctx.channel_layout;
+        frame.channels = ctx.channels;
+        frame.sample_rate = ctx.sample_rate;
+        frame.pts = self.next_pts.unwrap_or(0);
+        check(av_frame_get_buffer(frame, 0))?;
+
+        let ret = av_audio_fifo_peek(
+            self.fifo,
+            frame.extended_data as *mut *mut c_void,
+            nb_samples,
+        );
+        if let Err(e) = check(ret) {
+            av_frame_unref(frame);
+            return Err(e);
+        }
+
+        if frame_samples > nb_samples {
+            av_samples_set_silence(
+                frame.extended_data,
+                nb_samples,
+                frame_samples - nb_samples,
+                ctx.channels,
+                ctx.sample_fmt,
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,143 @@
+use crate::ff_error::*;
+use crate::*;
+use libc::ENOMEM;
+use log::error;
+use std::ffi::CString;
+use std::ptr;
+
+/// a bitstream filter such as h264_mp4toannexb or aac_adtstoasc, or a chain
+/// of them, which rewrites packets without decoding them
+pub struct BitstreamFilter {
+    bsf: *mut AVBSFContext,
+}
+
+impl Drop for BitstreamFilter {
+    fn drop(&mut self) {
+        unsafe { av_bsf_free(&mut self.bsf) }
+    }
+}
+
+impl BitstreamFilter {
+    /// a single filter, i.e. "h264_mp4toannexb", for packets of a stream with
+    /// params, whose timestamps are in time_base
+    pub fn with_name(name: &str, params: &Parameters, time_base: Rational) -> Result<Self> {
+        let c_name = CString::new(name)?;
+        unsafe {
+            let filter = av_bsf_get_by_name(c_name.as_ptr());
+            if filter.is_null() {
+                error!("failed to find bitstream filter {}", name);
+                return Err(Error::from_code(AVERROR_BSF_NOT_FOUND));
+            }
+
+            let mut bsf = ptr::null_mut();
+            check(av_bsf_alloc(filter, &mut bsf))?;
+            Self::init(bsf, params, time_base)
+        }
+    }
+
+    /// a comma separated chain of filters with options, i.e.
+    /// "h264_mp4toannexb,dump_extra=freq=keyframe", an empty chain passes
+    /// packets through unchanged
+    pub fn with_chain(chain: &str, params: &Parameters, time_base: Rational) -> Result<Self> {
+        let c_chain = CString::new(chain)?;
+        unsafe {
+            let mut bsf = ptr::null_mut();
+            if let Err(e) = check(av_bsf_list_parse_str(c_chain.as_ptr(), &mut bsf)) {
+                error!(
+                    "failed to parse bitstream filter chain \"{}\": {}",
+                    chain, e
+                );
+                return Err(e);
+            }
+            Self::init(bsf, params, time_base)
+        }
+    }
+
+    /// a filter or chain of filters for the packets of stream
+    pub fn with_stream(name: &str, stream: Stream) -> Result<Self> {
+        Self::with_chain(name, &stream.codec_parameters(), stream.time_base())
+    }
+
+    unsafe fn init(
+        mut bsf: *mut AVBSFContext,
+        params: &Parameters,
+        time_base: Rational,
+    ) -> Result<Self> {
+        if bsf.is_null() {
+            return Err(Error::Io(ENOMEM));
+        }
+
+        let ret = avcodec_parameters_copy((*bsf).par_in, params.as_ptr());
+        if let Err(e) = check(ret) {
+            av_bsf_free(&mut bsf);
+            return Err(e);
+        }
+        (*bsf).time_base_in = time_base.into();
+
+        if let Err(e) = check(av_bsf_init(bsf)) {
+            av_bsf_free(&mut bsf);
+            return Err(e);
+        }
+        Ok(BitstreamFilter { bsf })
+    }
+
+    /// the parameters of the filtered stream, i.e. with the extradata
+    /// converted, to be used for the output stream
+    pub fn output_parameters(&self) -> Parameters {
+        unsafe { Parameters::copy_from((*self.bsf).par_out) }
+    }
+
+    /// the time base of the filtered packets
+    pub fn output_time_base(&self) -> Rational {
+        unsafe { (*self.bsf).time_base_out.into() }
+    }
+
+    /// fails with Error::Again if receive_packet() is expected to be called
+    /// before the packet can be resent. the packet is referenced, not consumed.
+    pub fn send_packet(&mut self, packet: &Packet) -> Result<()> {
+        let mut packet = packet.clone();
+        unsafe { check(av_bsf_send_packet(self.bsf, packet.as_mut_ptr())).map(|_| ()) }
+    }
+
+    /// signal the end of stream, the buffered packets can then be received
+    /// with receive_packet() until it fails with Error::Eof
+    pub fn send_eof(&mut self) -> Result<()> {
+        unsafe { check(av_bsf_send_packet(self.bsf, ptr::null_mut())).map(|_| ()) }
+    }
+
+    /// fails with Error::Again if more packets are expected, Error::Eof if
+    /// the filter has been drained
+    pub fn receive_packet(&mut self, out_packet: &mut Packet) -> Result<()> {
+        out_packet.unref();
+        unsafe { check(av_bsf_receive_packet(self.bsf, out_packet.as_mut_ptr())).map(|_| ()) }
+    }
+
+    /// send the packet and receive all the packets that are ready
+    pub fn filter(&mut self, packet: &Packet) -> Result<Vec<Packet>> {
+        self.send_packet(packet)?;
+        self.receive_all()
+    }
+
+    /// signal the end of stream and receive all the buffered packets
+    pub fn drain(&mut self) -> Result<Vec<Packet>> {
+        self.send_eof()?;
+        self.receive_all()
+    }
+
+    /// drop the buffered packets, i.e. after seeking
+    pub fn flush(&mut self) {
+        unsafe { av_bsf_flush(self.bsf) }
+    }
+
+    fn receive_all(&mut self) -> Result<Vec<Packet>> {
+        let mut packets = Vec::new();
+        loop {
+            let mut packet = Packet::new();
+            match self.receive_packet(&mut packet) {
+                Ok(()) => packets.push(packet),
+                Err(Error::Again) | Err(Error::Eof) => return Ok(packets),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
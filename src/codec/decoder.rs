@@ -1,6 +1,6 @@
 use crate::ff_error::*;
 use crate::*;
-use libc::{c_char, EAGAIN};
+use libc::ENOMEM;
 use log::error;
 use std::ffi::CStr;
 use std::ops::{Deref, DerefMut};
@@ -36,71 +36,191 @@ impl DerefMut for Decoder {
 }
 
 impl Decoder {
-    pub fn with_stream(stream: Stream) -> Option<Self> {
+    pub fn with_stream(stream: Stream) -> Result<Self> {
+        Self::open(&stream.codec_parameters(), Some(stream.time_base()))
+    }
+
+    /// packet timestamps are assumed to be in the time base of the decoder,
+    /// use with_stream() when decoding packets of an Input
+    pub fn with_parameters(params: &Parameters) -> Result<Self> {
+        Self::open(params, None)
+    }
+
+    /// a decoder of the stream using codec, i.e. the one av_find_best_stream
+    /// found for it
+    pub(crate) unsafe fn with_stream_and_codec(
+        stream: Stream,
+        codec: *const AVCodec,
+    ) -> Result<Self> {
+        Self::open_codec(codec, &stream.codec_parameters(), Some(stream.time_base()))
+    }
+
+    fn open(params: &Parameters, pkt_time_base: Option<Rational>) -> Result<Self> {
         unsafe {
-            let codec = avcodec_find_decoder((*stream.codecpar).codec_id as u32);
+            let codec = avcodec_find_decoder(params.codec_id().into());
             if codec.is_null() {
-                error!(
-                    "failed to find decoder with codec_id:{}",
-                    (*stream.codecpar).codec_id
-                );
-                return None;
+                error!("failed to find decoder for {}", params.codec_id().name());
+                return Err(Error::DecoderNotFound);
             }
+            Self::open_codec(codec, params, pkt_time_base)
+        }
+    }
 
-            let mut codec_ctx = avcodec_alloc_context3(codec);
-            if codec_ctx.is_null() {
-                error!("avcodec_alloc_context3 failed");
-                return None;
-            }
+    unsafe fn open_codec(
+        codec: *const AVCodec,
+        params: &Parameters,
+        pkt_time_base: Option<Rational>,
+    ) -> Result<Self> {
+        let mut codec_ctx = avcodec_alloc_context3(codec);
+        if codec_ctx.is_null() {
+            return Err(Error::Io(ENOMEM));
+        }
 
-            let ret = avcodec_parameters_to_context(codec_ctx, stream.codecpar);
-            if ret < 0 {
-                ff_error!(ret, "avcodec_parameters_to_context failed");
-                avcodec_free_context(&mut codec_ctx);
-                return None;
-            }
+        let ret = avcodec_parameters_to_context(codec_ctx, params.as_ptr());
+        if let Err(e) = check(ret) {
+            avcodec_free_context(&mut codec_ctx);
+            return Err(e);
+        }
+        if let Some(time_base) = pkt_time_base {
+            (*codec_ctx).pkt_timebase = time_base.into();
+        }
 
-            let ret = avcodec_open2(codec_ctx, codec, ptr::null_mut());
-            if ret < 0 {
-                ff_error!(ret, "avcodec_open2 failed");
-                avcodec_free_context(&mut codec_ctx);
-                return None;
-            }
+        let ret = avcodec_open2(codec_ctx, codec, ptr::null_mut());
+        if let Err(e) = check(ret) {
+            avcodec_free_context(&mut codec_ctx);
+            return Err(e);
+        }
 
-            log::info!(
-                "created decoder({:?}) for stream {}",
-                CStr::from_ptr((*codec).name),
-                stream.id
-            );
+        log::info!("created decoder({:?})", CStr::from_ptr((*codec).name));
 
-            Some(Decoder { codec_ctx })
+        Ok(Decoder { codec_ctx })
+    }
+
+    /// fails with Error::Again if dequeue_frame() is expected to be called and
+    /// the packet to be resent, Error::Eof if the decoder has been flushed and
+    /// no more packets can be sent, other errors are legitimate decoding errors.
+    pub fn enqueue_packet(&self, in_packet: &Packet) -> Result<()> {
+        unsafe { check(avcodec_send_packet(self.codec_ctx, in_packet.as_ptr())).map(|_| ()) }
+    }
+
+    /// fails with Error::Again if more packets are expected, Error::Eof if the
+    /// decoder has been flushed and there will be no more output frames, other
+    /// errors are legitimate decoding errors.
+    pub fn dequeue_frame(&self, out_frame: &mut Frame) -> Result<()> {
+        // release whatever the frame held from a previous call
+        out_frame.unref();
+        unsafe {
+            check(avcodec_receive_frame(
+                self.codec_ctx,
+                out_frame.as_mut_ptr(),
+            ))
+            .map(|_| ())
         }
     }
 
-    /// return 0 on success, AVERROR(EAGAIN) if dequeue_frame() is expected
-    /// to be called and packet to be resent, AVERROR_EOF if the decoder
-    /// has been flushed and not more packets can be sent, other negative values
-    /// are legitimate decoding errors.
-    pub fn enqueue_packet(&self, in_packet: &Packet) -> i32 {
+    /// signal the end of stream, the buffered frames can then be received
+    /// with dequeue_frame() until it fails with Error::Eof
+    pub fn send_eof(&self) -> Result<()> {
         unsafe {
-            let ret = avcodec_send_packet(self.codec_ctx, &**in_packet);
-            if ret < 0 && ret != AVERROR(EAGAIN) && ret != AVERROR_EOF {
-                ff_error!(ret, "failed to call avcodec_send_packet");
+            match check(avcodec_send_packet(self.codec_ctx, ptr::null())) {
+                // already in draining mode
+                Err(Error::Eof) => Ok(()),
+                r => r.map(|_| ()),
             }
-            ret
         }
     }
 
-    /// return 0 on success, AVERROR(EAGAIN) if more AVPackets are expected,
-    /// AVERROR_EOF if the decoder has been flushed and there will be not more
-    /// output frames, other negative values are legitimate decoding errors.
-    pub fn dequeue_frame(&self, out_frame: &mut Frame) -> i32 {
-        unsafe {
-            let ret = avcodec_receive_frame(self.codec_ctx, &mut **out_frame);
-            if ret < 0 && ret != AVERROR(EAGAIN) && ret != AVERROR_EOF {
-                ff_error!(ret, "failed to call avcodec_send_packet");
+    /// drop the buffered frames and reset the decoder, must be called after
+    /// seeking or to decode again after the decoder has been drained
+    pub fn flush(&self) {
+        unsafe { avcodec_flush_buffers(self.codec_ctx) }
+    }
+
+    /// send the packet and iterate over the frames that are ready to be output.
+    /// if the decoder can't take the packet before its pending frames are
+    /// received, it is resent once they have been, so the iterator must be
+    /// consumed to the end for the packet to be decoded.
+    pub fn decode<'a>(&'a mut self, packet: &'a Packet) -> Frames<'a> {
+        match self.enqueue_packet(packet) {
+            Ok(()) => Frames::new(self, None, None),
+            Err(Error::Again) => Frames::new(self, Some(packet), None),
+            Err(e) => Frames::new(self, None, Some(e)),
+        }
+    }
+
+    /// signal the end of stream and iterate over all the buffered frames, i.e.
+    /// the last frames of streams with B-frames
+    pub fn drain(&mut self) -> Frames<'_> {
+        let pending_error = self.send_eof().err();
+        Frames::new(self, None, pending_error)
+    }
+}
+
+pub struct Frames<'a> {
+    decoder: &'a Decoder,
+    // rejected with Error::Again, sent again once the decoder asks for input
+    resend_packet: Option<&'a Packet>,
+    pending_error: Option<Error>,
+    done: bool,
+}
+
+impl<'a> Frames<'a> {
+    fn new(
+        decoder: &'a Decoder,
+        resend_packet: Option<&'a Packet>,
+        pending_error: Option<Error>,
+    ) -> Self {
+        Frames {
+            decoder,
+            resend_packet,
+            pending_error,
+            done: false,
+        }
+    }
+}
+
+/// ends when the decoder needs more input or has been fully drained, or after
+/// the first decoding error
+impl<'a> Iterator for Frames<'a> {
+    type Item = Result<Frame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        if let Some(e) = self.pending_error.take() {
+            self.done = true;
+            return Some(Err(e));
+        }
+
+        let mut frame = Frame::new();
+        loop {
+            match self.decoder.dequeue_frame(&mut frame) {
+                Ok(()) => return Some(Ok(frame)),
+                Err(Error::Again) => {
+                    let packet = match self.resend_packet.take() {
+                        Some(packet) => packet,
+                        None => {
+                            self.done = true;
+                            return None;
+                        }
+                    };
+                    // the decoder has room for it now
+                    if let Err(e) = self.decoder.enqueue_packet(packet) {
+                        self.done = true;
+                        return Some(Err(e));
+                    }
+                }
+                Err(Error::Eof) => {
+                    self.done = true;
+                    return None;
+                }
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
             }
-            ret
         }
     }
 }
@@ -0,0 +1,427 @@
+use super::audio_fifo::AudioFifo;
+use crate::ff_error::*;
+use crate::*;
+use libc::{c_int, EINVAL, ENOMEM};
+use log::{error, warn};
+use std::ffi::{CStr, CString};
+use std::ops::{Deref, DerefMut};
+use std::ptr;
+
+pub struct Encoder {
+    codec_ctx: *mut AVCodecContext,
+    // set for audio encoders that only accept frames of frame_size samples
+    audio_fifo: Option<AudioFifo>,
+}
+
+impl Drop for Encoder {
+    fn drop(&mut self) {
+        unsafe {
+            if !self.codec_ctx.is_null() {
+                avcodec_free_context(&mut self.codec_ctx);
+            }
+        }
+    }
+}
+
+impl Deref for Encoder {
+    type Target = AVCodecContext;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.codec_ctx }
+    }
+}
+
+impl DerefMut for Encoder {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut *self.codec_ctx }
+    }
+}
+
+impl Encoder {
+    /// fails with Error::Again if receive_packet() is expected to be called
+    /// before the frame can be resent, Error::Eof if the encoder has been
+    /// flushed and no more frames can be sent.
+    ///
+    /// audio encoders with a fixed frame_size accept frames with any number of
+    /// samples, they are buffered and never fail with Error::Again, samples the
+    /// encoder isn't ready for are sent by the next call.
+    pub fn send_frame(&mut self, in_frame: &Frame) -> Result<()> {
+        match self.audio_fifo.as_mut() {
+            Some(fifo) => {
+                fifo.write(self.codec_ctx, in_frame)?;
+                match fifo.send_to(self.codec_ctx, false) {
+                    Err(Error::Again) => Ok(()),
+                    r => r,
+                }
+            }
+            None => unsafe {
+                check(avcodec_send_frame(self.codec_ctx, in_frame.as_ptr())).map(|_| ())
+            },
+        }
+    }
+
+    /// fails with Error::Again if more frames are expected, Error::Eof if the
+    /// encoder has been flushed and there will be no more output packets.
+    pub fn receive_packet(&self, out_packet: &mut Packet) -> Result<()> {
+        unsafe {
+            check(avcodec_receive_packet(
+                self.codec_ctx,
+                out_packet.as_mut_ptr(),
+            ))
+            .map(|_| ())
+        }
+    }
+
+    /// enter draining mode, receive_packet() should then be called until it
+    /// fails with Error::Eof to get the buffered packets. for audio encoders
+    /// with buffered samples this fails with Error::Again until all of them
+    /// have been sent, call receive_packet() and retry.
+    pub fn flush(&mut self) -> Result<()> {
+        if let Some(fifo) = self.audio_fifo.as_mut() {
+            fifo.send_to(self.codec_ctx, true)?;
+        }
+        unsafe { check(avcodec_send_frame(self.codec_ctx, ptr::null())).map(|_| ()) }
+    }
+
+    /// time base of the frames sent to and packets received from the encoder
+    pub fn time_base(&self) -> Rational {
+        self.deref().time_base.into()
+    }
+
+    pub(crate) fn as_ptr(&self) -> *const AVCodecContext {
+        self.codec_ctx
+    }
+}
+
+pub struct EncoderBuilder {
+    codec: *const AVCodec,
+    width: c_int,
+    height: c_int,
+    pix_fmt: PixelFormat,
+    time_base: Option<Rational>,
+    frame_rate: Option<Rational>,
+    sample_aspect_ratio: Option<Rational>,
+    bit_rate: i64,
+    gop_size: Option<c_int>,
+    max_b_frames: Option<c_int>,
+    sample_rate: c_int,
+    channel_layout: u64,
+    sample_fmt: SampleFormat,
+    quality: Option<f32>,
+    global_header: bool,
+    options: Dictionary,
+}
+
+impl EncoderBuilder {
+    /// i.e. "libx264", "mpeg4"
+    pub fn with_name(name: &str) -> Result<Self> {
+        let c_name = CString::new(name)?;
+        let codec = unsafe { avcodec_find_encoder_by_name(c_name.as_ptr()) };
+        Self::with_codec(codec)
+    }
+
+    pub fn with_codec_id(codec_id: CodecId) -> Result<Self> {
+        let codec = unsafe { avcodec_find_encoder(codec_id.into()) };
+        Self::with_codec(codec)
+    }
+
+    fn with_codec(codec: *const AVCodec) -> Result<Self> {
+        if codec.is_null() {
+            return Err(Error::from_code(AVERROR_ENCODER_NOT_FOUND));
+        }
+
+        Ok(EncoderBuilder {
+            codec,
+            width: 0,
+            height: 0,
+            pix_fmt: PixelFormat::None,
+            time_base: None,
+            frame_rate: None,
+            sample_aspect_ratio: None,
+            bit_rate: 0,
+            gop_size: None,
+            max_b_frames: None,
+            sample_rate: 0,
+            channel_layout: 0,
+            sample_fmt: SampleFormat::None,
+            quality: None,
+            global_header: false,
+            options: Dictionary::new(),
+        })
+    }
+
+    /// the pixel formats the codec accepts, empty if unknown
+    pub fn supported_pixel_formats(&self) -> Vec<PixelFormat> {
+        unsafe {
+            terminated_list((*self.codec).pix_fmts, AVPixelFormat_AV_PIX_FMT_NONE)
+                .into_iter()
+                .map(PixelFormat::from)
+                .collect()
+        }
+    }
+
+    /// the sample formats the codec accepts, empty if unknown
+    pub fn supported_sample_formats(&self) -> Vec<SampleFormat> {
+        unsafe {
+            terminated_list((*self.codec).sample_fmts, AVSampleFormat_AV_SAMPLE_FMT_NONE)
+                .into_iter()
+                .map(SampleFormat::from)
+                .collect()
+        }
+    }
+
+    /// the sample rates the codec accepts, empty if any
+    pub fn supported_sample_rates(&self) -> Vec<u32> {
+        unsafe {
+            terminated_list((*self.codec).supported_samplerates, 0)
+                .into_iter()
+                .map(|rate| rate as u32)
+                .collect()
+        }
+    }
+
+    pub fn size(mut self, width: u32, height: u32) -> Self {
+        self.width = width as c_int;
+        self.height = height as c_int;
+        self
+    }
+
+    /// defaults to the first pixel format supported by the codec
+    pub fn pixel_format(mut self, pix_fmt: PixelFormat) -> Self {
+        self.pix_fmt = pix_fmt;
+        self
+    }
+
+    /// time base of the frame timestamps, defaults to 1/frame_rate for video
+    /// and 1/sample_rate for audio
+    pub fn time_base(mut self, time_base: Rational) -> Self {
+        self.time_base = Some(time_base);
+        self
+    }
+
+    pub fn frame_rate(mut self, frame_rate: Rational) -> Self {
+        self.frame_rate = Some(frame_rate);
+        self
+    }
+
+    /// the width of a pixel divided by its height, for anamorphic video,
+    /// square pixels by default
+    pub fn sample_aspect_ratio(mut self, sample_aspect_ratio: Rational) -> Self {
+        self.sample_aspect_ratio = Some(sample_aspect_ratio);
+        self
+    }
+
+    /// in bits per second, 0 leaves rate control to the codec (i.e. crf for libx264)
+    pub fn bit_rate(mut self, bit_rate: i64) -> Self {
+        self.bit_rate = bit_rate;
+        self
+    }
+
+    /// the maximum distance between two keyframes
+    pub fn gop_size(mut self, gop_size: u32) -> Self {
+        self.gop_size = Some(gop_size as c_int);
+        self
+    }
+
+    pub fn max_b_frames(mut self, max_b_frames: u32) -> Self {
+        self.max_b_frames = Some(max_b_frames as c_int);
+        self
+    }
+
+    /// audio only, i.e. 44100, 48000
+    pub fn sample_rate(mut self, sample_rate: u32) -> Self {
+        self.sample_rate = sample_rate as c_int;
+        self
+    }
+
+    /// audio only, AV_CH_LAYOUT_* bitmask, defaults to stereo
+    pub fn channel_layout(mut self, channel_layout: u64) -> Self {
+        self.channel_layout = channel_layout;
+        self
+    }
+
+    /// audio only, defaults to the first sample format supported by the codec,
+    /// s16p/fltp for libmp3lame and fltp for aac
+    pub fn sample_format(mut self, sample_fmt: SampleFormat) -> Self {
+        self.sample_fmt = sample_fmt;
+        self
+    }
+
+    /// variable bitrate quality instead of a fixed bit_rate, the scale is codec
+    /// specific: 0 (best) to 9 for libmp3lame, 0.1 to 2 for aac
+    pub fn quality(mut self, quality: f32) -> Self {
+        self.quality = Some(quality);
+        self
+    }
+
+    /// put the codec headers in extradata instead of every keyframe, required
+    /// by muxers with the AVFMT_GLOBALHEADER flag (mp4, mov, matroska)
+    pub fn global_header(mut self, global_header: bool) -> Self {
+        self.global_header = global_header;
+        self
+    }
+
+    /// generic or codec-private option, passed to avcodec_open2()
+    pub fn option(mut self, key: &str, value: &str) -> Result<Self> {
+        self.options.insert(key, value)?;
+        Ok(self)
+    }
+
+    /// replaces the options set so far
+    pub fn options(mut self, options: Dictionary) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// libx264 preset, i.e. "ultrafast", "veryfast", "medium", "slow"
+    pub fn preset(self, preset: &str) -> Result<Self> {
+        self.option("preset", preset)
+    }
+
+    /// libx264 tune, i.e. "film", "animation", "zerolatency"
+    pub fn tune(self, tune: &str) -> Result<Self> {
+        self.option("tune", tune)
+    }
+
+    /// libx264 constant rate factor, 0-51, lower is better quality
+    pub fn crf(self, crf: f32) -> Result<Self> {
+        self.option("crf", &crf.to_string())
+    }
+
+    pub fn build(self) -> Result<Encoder> {
+        unsafe {
+            let codec_ctx = avcodec_alloc_context3(self.codec);
+            if codec_ctx.is_null() {
+                return Err(Error::Io(ENOMEM));
+            }
+
+            // freed on the error paths below
+            let mut encoder = Encoder {
+                codec_ctx,
+                audio_fifo: None,
+            };
+
+            let media_type = (*self.codec).type_;
+            if media_type == AVMediaType_AVMEDIA_TYPE_AUDIO {
+                self.configure_audio(codec_ctx)?;
+            } else {
+                self.configure_video(codec_ctx)?;
+            }
+
+            if self.bit_rate > 0 {
+                (*codec_ctx).bit_rate = self.bit_rate;
+            }
+            if let Some(quality) = self.quality {
+                (*codec_ctx).flags |= AV_CODEC_FLAG_QSCALE as c_int;
+                (*codec_ctx).global_quality = (quality * FF_QP2LAMBDA as f32) as c_int;
+            }
+            if self.global_header {
+                (*codec_ctx).flags |= AV_CODEC_FLAG_GLOBAL_HEADER as c_int;
+            }
+
+            let mut options = self.options.clone();
+            check(avcodec_open2(
+                codec_ctx,
+                self.codec,
+                options.as_mut_ptr_ref(),
+            ))?;
+            for (key, _) in &options {
+                warn!("encoder option not found: {}", key);
+            }
+
+            let variable_frame_size =
+                (*self.codec).capabilities & AV_CODEC_CAP_VARIABLE_FRAME_SIZE as c_int != 0;
+            if media_type == AVMediaType_AVMEDIA_TYPE_AUDIO
+                && (*codec_ctx).frame_size > 0
+                && !variable_frame_size
+            {
+                encoder.audio_fifo = Some(AudioFifo::new(codec_ctx)?);
+            }
+
+            log::info!("created encoder({:?})", CStr::from_ptr((*self.codec).name));
+
+            Ok(encoder)
+        }
+    }
+
+    unsafe fn configure_video(&self, codec_ctx: *mut AVCodecContext) -> Result<()> {
+        if self.width <= 0 || self.height <= 0 {
+            error!("invalid encoder size {}x{}", self.width, self.height);
+            return Err(Error::Io(EINVAL));
+        }
+        let time_base = match (self.time_base, self.frame_rate) {
+            (Some(time_base), _) => time_base,
+            (None, Some(frame_rate)) => frame_rate.invert(),
+            (None, None) => {
+                error!("either time_base or frame_rate must be set for the encoder");
+                return Err(Error::Io(EINVAL));
+            }
+        };
+
+        let ctx = &mut *codec_ctx;
+        ctx.width = self.width;
+        ctx.height = self.height;
+        ctx.time_base = time_base.into();
+        if let Some(frame_rate) = self.frame_rate {
+            ctx.framerate = frame_rate.into();
+        }
+        if let Some(sar) = self.sample_aspect_ratio {
+            ctx.sample_aspect_ratio = sar.into();
+        }
+        ctx.pix_fmt = if self.pix_fmt != PixelFormat::None {
+            self.pix_fmt.into()
+        } else if !(*self.codec).pix_fmts.is_null() {
+            *(*self.codec).pix_fmts
+        } else {
+            AVPixelFormat_AV_PIX_FMT_YUV420P
+        };
+        if let Some(gop_size) = self.gop_size {
+            ctx.gop_size = gop_size;
+        }
+        if let Some(max_b_frames) = self.max_b_frames {
+            ctx.max_b_frames = max_b_frames;
+        }
+        Ok(())
+    }
+
+    unsafe fn configure_audio(&self, codec_ctx: *mut AVCodecContext) -> Result<()> {
+        if self.sample_rate <= 0 {
+            error!("sample_rate must be set for the audio encoder");
+            return Err(Error::Io(EINVAL));
+        }
+
+        let ctx = &mut *codec_ctx;
+        ctx.sample_rate = self.sample_rate;
+        ctx.channel_layout = if self.channel_layout != 0 {
+            self.channel_layout
+        } else {
+            AV_CH_LAYOUT_STEREO as u64
+        };
+        ctx.channels = av_get_channel_layout_nb_channels(ctx.channel_layout);
+        ctx.sample_fmt = if self.sample_fmt != SampleFormat::None {
+            self.sample_fmt.into()
+        } else if !(*self.codec).sample_fmts.is_null() {
+            *(*self.codec).sample_fmts
+        } else {
+            AVSampleFormat_AV_SAMPLE_FMT_S16
+        };
+        ctx.time_base = self
+            .time_base
+            .unwrap_or_else(|| Rational::new(1, self.sample_rate))
+            .into();
+        Ok(())
+    }
+}
+
+// the values of a list terminated by end, as found in AVCodec
+unsafe fn terminated_list<T: Copy + PartialEq>(mut list: *const T, end: T) -> Vec<T> {
+    let mut values = Vec::new();
+    if list.is_null() {
+        return values;
+    }
+    while *list != end {
+        values.push(*list);
+        list = list.add(1);
+    }
+    values
+}
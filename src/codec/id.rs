@@ -0,0 +1,196 @@
+use crate::*;
+use std::ffi::CStr;
+
+/// the AV_CODEC_ID_* values, codecs without a variant are kept in Other
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CodecId {
+    None,
+    Mpeg2Video,
+    Mpeg4,
+    RawVideo,
+    Mjpeg,
+    H264,
+    Png,
+    Bmp,
+    Gif,
+    Vp8,
+    Vp9,
+    Webp,
+    Hevc,
+    Av1,
+    PcmS16le,
+    PcmF32le,
+    Mp2,
+    Mp3,
+    Aac,
+    Ac3,
+    Vorbis,
+    Flac,
+    Eac3,
+    Opus,
+    DvdSubtitle,
+    MovText,
+    Ass,
+    Webvtt,
+    Subrip,
+    Ttf,
+    Otf,
+    /// any value without a variant of its own
+    Other(AVCodecID),
+}
+
+impl From<AVCodecID> for CodecId {
+    fn from(value: AVCodecID) -> Self {
+        match value {
+            AVCodecID_AV_CODEC_ID_NONE => CodecId::None,
+            AVCodecID_AV_CODEC_ID_MPEG2VIDEO => CodecId::Mpeg2Video,
+            AVCodecID_AV_CODEC_ID_MPEG4 => CodecId::Mpeg4,
+            AVCodecID_AV_CODEC_ID_RAWVIDEO => CodecId::RawVideo,
+            AVCodecID_AV_CODEC_ID_MJPEG => CodecId::Mjpeg,
+            AVCodecID_AV_CODEC_ID_H264 => CodecId::H264,
+            AVCodecID_AV_CODEC_ID_PNG => CodecId::Png,
+            AVCodecID_AV_CODEC_ID_BMP => CodecId::Bmp,
+            AVCodecID_AV_CODEC_ID_GIF => CodecId::Gif,
+            AVCodecID_AV_CODEC_ID_VP8 => CodecId::Vp8,
+            AVCodecID_AV_CODEC_ID_VP9 => CodecId::Vp9,
+            AVCodecID_AV_CODEC_ID_WEBP => CodecId::Webp,
+            AVCodecID_AV_CODEC_ID_HEVC => CodecId::Hevc,
+            AVCodecID_AV_CODEC_ID_AV1 => CodecId::Av1,
+            AVCodecID_AV_CODEC_ID_PCM_S16LE => CodecId::PcmS16le,
+            AVCodecID_AV_CODEC_ID_PCM_F32LE => CodecId::PcmF32le,
+            AVCodecID_AV_CODEC_ID_MP2 => CodecId::Mp2,
+            AVCodecID_AV_CODEC_ID_MP3 => CodecId::Mp3,
+            AVCodecID_AV_CODEC_ID_AAC => CodecId::Aac,
+            AVCodecID_AV_CODEC_ID_AC3 => CodecId::Ac3,
+            AVCodecID_AV_CODEC_ID_VORBIS => CodecId::Vorbis,
+            AVCodecID_AV_CODEC_ID_FLAC => CodecId::Flac,
+            AVCodecID_AV_CODEC_ID_EAC3 => CodecId::Eac3,
+            AVCodecID_AV_CODEC_ID_OPUS => CodecId::Opus,
+            AVCodecID_AV_CODEC_ID_DVD_SUBTITLE => CodecId::DvdSubtitle,
+            AVCodecID_AV_CODEC_ID_MOV_TEXT => CodecId::MovText,
+            AVCodecID_AV_CODEC_ID_ASS => CodecId::Ass,
+            AVCodecID_AV_CODEC_ID_WEBVTT => CodecId::Webvtt,
+            AVCodecID_AV_CODEC_ID_SUBRIP => CodecId::Subrip,
+            AVCodecID_AV_CODEC_ID_TTF => CodecId::Ttf,
+            AVCodecID_AV_CODEC_ID_OTF => CodecId::Otf,
+            value => CodecId::Other(value),
+        }
+    }
+}
+
+impl From<CodecId> for AVCodecID {
+    fn from(value: CodecId) -> Self {
+        match value {
+            CodecId::None => AVCodecID_AV_CODEC_ID_NONE,
+            CodecId::Mpeg2Video => AVCodecID_AV_CODEC_ID_MPEG2VIDEO,
+            CodecId::Mpeg4 => AVCodecID_AV_CODEC_ID_MPEG4,
+            CodecId::RawVideo => AVCodecID_AV_CODEC_ID_RAWVIDEO,
+            CodecId::Mjpeg => AVCodecID_AV_CODEC_ID_MJPEG,
+            CodecId::H264 => AVCodecID_AV_CODEC_ID_H264,
+            CodecId::Png => AVCodecID_AV_CODEC_ID_PNG,
+            CodecId::Bmp => AVCodecID_AV_CODEC_ID_BMP,
+            CodecId::Gif => AVCodecID_AV_CODEC_ID_GIF,
+            CodecId::Vp8 => AVCodecID_AV_CODEC_ID_VP8,
+            CodecId::Vp9 => AVCodecID_AV_CODEC_ID_VP9,
+            CodecId::Webp => AVCodecID_AV_CODEC_ID_WEBP,
+            CodecId::Hevc => AVCodecID_AV_CODEC_ID_HEVC,
+            CodecId::Av1 => AVCodecID_AV_CODEC_ID_AV1,
+            CodecId::PcmS16le => AVCodecID_AV_CODEC_ID_PCM_S16LE,
+            CodecId::PcmF32le => AVCodecID_AV_CODEC_ID_PCM_F32LE,
+            CodecId::Mp2 => AVCodecID_AV_CODEC_ID_MP2,
+            CodecId::Mp3 => AVCodecID_AV_CODEC_ID_MP3,
+            CodecId::Aac => AVCodecID_AV_CODEC_ID_AAC,
+            CodecId::Ac3 => AVCodecID_AV_CODEC_ID_AC3,
+            CodecId::Vorbis => AVCodecID_AV_CODEC_ID_VORBIS,
+            CodecId::Flac => AVCodecID_AV_CODEC_ID_FLAC,
+            CodecId::Eac3 => AVCodecID_AV_CODEC_ID_EAC3,
+            CodecId::Opus => AVCodecID_AV_CODEC_ID_OPUS,
+            CodecId::DvdSubtitle => AVCodecID_AV_CODEC_ID_DVD_SUBTITLE,
+            CodecId::MovText => AVCodecID_AV_CODEC_ID_MOV_TEXT,
+            CodecId::Ass => AVCodecID_AV_CODEC_ID_ASS,
+            CodecId::Webvtt => AVCodecID_AV_CODEC_ID_WEBVTT,
+            CodecId::Subrip => AVCodecID_AV_CODEC_ID_SUBRIP,
+            CodecId::Ttf => AVCodecID_AV_CODEC_ID_TTF,
+            CodecId::Otf => AVCodecID_AV_CODEC_ID_OTF,
+            CodecId::Other(value) => value,
+        }
+    }
+}
+
+impl CodecId {
+    /// i.e. "h264", "aac"
+    pub fn name(&self) -> &'static str {
+        unsafe {
+            let name = avcodec_get_name((*self).into());
+            if name.is_null() {
+                "unknown"
+            } else {
+                CStr::from_ptr(name).to_str().unwrap_or("unknown")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn av_codec_id_round_trip() {
+        let ids = [
+            CodecId::None,
+            CodecId::Mpeg2Video,
+            CodecId::Mpeg4,
+            CodecId::RawVideo,
+            CodecId::Mjpeg,
+            CodecId::H264,
+            CodecId::Png,
+            CodecId::Bmp,
+            CodecId::Gif,
+            CodecId::Vp8,
+            CodecId::Vp9,
+            CodecId::Webp,
+            CodecId::Hevc,
+            CodecId::Av1,
+            CodecId::PcmS16le,
+            CodecId::PcmF32le,
+            CodecId::Mp2,
+            CodecId::Mp3,
+            CodecId::Aac,
+            CodecId::Ac3,
+            CodecId::Vorbis,
+            CodecId::Flac,
+            CodecId::Eac3,
+            CodecId::Opus,
+            CodecId::DvdSubtitle,
+            CodecId::MovText,
+            CodecId::Ass,
+            CodecId::Webvtt,
+            CodecId::Subrip,
+            CodecId::Ttf,
+            CodecId::Otf,
+        ];
+        for id in ids.iter() {
+            assert_eq!(CodecId::from(AVCodecID::from(*id)), *id);
+        }
+    }
+
+    #[test]
+    fn known_values() {
+        assert_eq!(CodecId::from(AVCodecID_AV_CODEC_ID_H264), CodecId::H264);
+        assert_eq!(AVCodecID::from(CodecId::Aac), AVCodecID_AV_CODEC_ID_AAC);
+    }
+
+    #[test]
+    fn other_keeps_its_value() {
+        let value = AVCodecID_AV_CODEC_ID_H264 + 1;
+        assert_eq!(CodecId::from(value), CodecId::Other(value));
+        assert_eq!(AVCodecID::from(CodecId::Other(value)), value);
+    }
+
+    #[test]
+    fn name() {
+        assert_eq!(CodecId::H264.name(), "h264");
+        assert_eq!(CodecId::Aac.name(), "aac");
+    }
+}
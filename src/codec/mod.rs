@@ -1,2 +1,14 @@
+mod audio_fifo;
+pub mod bitstream_filter;
 pub mod decoder;
-pub use self::decoder::Decoder;
+pub mod encoder;
+pub mod id;
+pub mod parameters;
+pub mod parser;
+
+pub use self::bitstream_filter::BitstreamFilter;
+pub use self::decoder::{Decoder, Frames};
+pub use self::encoder::{Encoder, EncoderBuilder};
+pub use self::id::CodecId;
+pub use self::parameters::Parameters;
+pub use self::parser::Parser;
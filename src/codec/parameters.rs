@@ -0,0 +1,179 @@
+use crate::ff_error::*;
+use crate::*;
+use std::slice;
+
+/// an owned copy of AVCodecParameters, describes the encoded data of a stream
+pub struct Parameters {
+    par: *mut AVCodecParameters,
+}
+
+impl Drop for Parameters {
+    fn drop(&mut self) {
+        unsafe {
+            avcodec_parameters_free(&mut self.par);
+        }
+    }
+}
+
+impl Clone for Parameters {
+    fn clone(&self) -> Self {
+        unsafe { Parameters::copy_from(self.par) }
+    }
+}
+
+impl Default for Parameters {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Parameters {
+    pub fn new() -> Self {
+        unsafe {
+            let par = avcodec_parameters_alloc();
+            if par.is_null() {
+                panic!("avcodec_parameters_alloc failed");
+            }
+            Parameters { par }
+        }
+    }
+
+    /// the parameters of the stream an encoder produces
+    pub fn from_encoder(encoder: &Encoder) -> Result<Self> {
+        let params = Parameters::new();
+        unsafe {
+            check(avcodec_parameters_from_context(
+                params.par,
+                encoder.as_ptr(),
+            ))?;
+        }
+        Ok(params)
+    }
+
+    pub(crate) unsafe fn copy_from(src: *const AVCodecParameters) -> Self {
+        let params = Parameters::new();
+        if let Err(e) = check(avcodec_parameters_copy(params.par, src)) {
+            panic!("avcodec_parameters_copy failed: {}", e);
+        }
+        params
+    }
+
+    pub fn media_type(&self) -> MediaType {
+        self.raw().codec_type.into()
+    }
+
+    pub fn codec_id(&self) -> CodecId {
+        self.raw().codec_id.into()
+    }
+
+    /// the fourcc of the codec in the container, 0 if not set
+    pub fn codec_tag(&self) -> u32 {
+        self.raw().codec_tag
+    }
+
+    pub fn set_codec_tag(&mut self, codec_tag: u32) {
+        self.raw_mut().codec_tag = codec_tag;
+    }
+
+    /// video only
+    pub fn pixel_format(&self) -> PixelFormat {
+        match self.media_type() {
+            MediaType::Video => PixelFormat::from(self.raw().format),
+            _ => PixelFormat::None,
+        }
+    }
+
+    /// audio only
+    pub fn sample_format(&self) -> SampleFormat {
+        match self.media_type() {
+            MediaType::Audio => SampleFormat::from(self.raw().format),
+            _ => SampleFormat::None,
+        }
+    }
+
+    /// in bits per second, 0 if unknown
+    pub fn bit_rate(&self) -> i64 {
+        self.raw().bit_rate
+    }
+
+    pub fn profile(&self) -> i32 {
+        self.raw().profile
+    }
+
+    pub fn level(&self) -> i32 {
+        self.raw().level
+    }
+
+    /// codec specific out-of-band data, i.e. the avcC box of H.264 in mp4
+    pub fn extradata(&self) -> &[u8] {
+        let par = self.raw();
+        if par.extradata.is_null() || par.extradata_size <= 0 {
+            &[]
+        } else {
+            unsafe { slice::from_raw_parts(par.extradata, par.extradata_size as usize) }
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.raw().width.max(0) as u32
+    }
+
+    pub fn height(&self) -> u32 {
+        self.raw().height.max(0) as u32
+    }
+
+    /// 0/0 if unknown
+    pub fn sample_aspect_ratio(&self) -> Rational {
+        self.raw().sample_aspect_ratio.into()
+    }
+
+    pub fn color_range(&self) -> AVColorRange {
+        self.raw().color_range
+    }
+
+    pub fn color_primaries(&self) -> AVColorPrimaries {
+        self.raw().color_primaries
+    }
+
+    pub fn color_transfer_characteristic(&self) -> AVColorTransferCharacteristic {
+        self.raw().color_trc
+    }
+
+    pub fn color_space(&self) -> AVColorSpace {
+        self.raw().color_space
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.raw().sample_rate.max(0) as u32
+    }
+
+    pub fn channels(&self) -> u32 {
+        self.raw().channels.max(0) as u32
+    }
+
+    /// AV_CH_LAYOUT_* bitmask, 0 if unknown
+    pub fn channel_layout(&self) -> u64 {
+        self.raw().channel_layout
+    }
+
+    /// number of samples per audio frame, 0 if variable or unknown
+    pub fn frame_size(&self) -> u32 {
+        self.raw().frame_size.max(0) as u32
+    }
+
+    pub fn as_ptr(&self) -> *const AVCodecParameters {
+        self.par
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut AVCodecParameters {
+        self.par
+    }
+
+    fn raw(&self) -> &AVCodecParameters {
+        unsafe { &*self.par }
+    }
+
+    fn raw_mut(&mut self) -> &mut AVCodecParameters {
+        unsafe { &mut *self.par }
+    }
+}
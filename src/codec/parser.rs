@@ -0,0 +1,154 @@
+use crate::ff_error::*;
+use crate::*;
+use libc::{c_int, ENOMEM};
+use log::error;
+use std::ptr;
+
+/// splits a raw elementary stream (i.e. H.264 Annex-B, HEVC, ADTS AAC, MP3)
+/// into packets that can be sent to a Decoder. the bytes can be fed in chunks
+/// of any size, packets are output once they are complete.
+pub struct Parser {
+    parser: *mut AVCodecParserContext,
+    // required by av_parser_parse2, parsers store stream properties in it
+    codec_ctx: *mut AVCodecContext,
+}
+
+impl Drop for Parser {
+    fn drop(&mut self) {
+        unsafe {
+            if !self.parser.is_null() {
+                av_parser_close(self.parser);
+            }
+            avcodec_free_context(&mut self.codec_ctx);
+        }
+    }
+}
+
+impl Parser {
+    /// fails with Error::ParserNotFound if libavcodec has no parser for codec_id
+    pub fn new(codec_id: CodecId) -> Result<Self> {
+        unsafe {
+            let parser = av_parser_init(AVCodecID::from(codec_id) as c_int);
+            if parser.is_null() {
+                error!("failed to find parser for {}", codec_id.name());
+                return Err(Error::ParserNotFound);
+            }
+
+            let codec_ctx = avcodec_alloc_context3(ptr::null());
+            if codec_ctx.is_null() {
+                av_parser_close(parser);
+                return Err(Error::Io(ENOMEM));
+            }
+            (*codec_ctx).codec_id = codec_id.into();
+
+            Ok(Parser { parser, codec_ctx })
+        }
+    }
+
+    /// parse as much of data as needed to complete a packet, returns the
+    /// number of bytes consumed and the packet if one was completed. call
+    /// again with the rest of data until it is all consumed. an empty data
+    /// outputs the last buffered packet at the end of stream.
+    ///
+    /// pts and dts are those of the first byte of data, if known.
+    pub fn parse_next(
+        &mut self,
+        data: &[u8],
+        pts: Option<i64>,
+        dts: Option<i64>,
+    ) -> Result<(usize, Option<Packet>)> {
+        unsafe {
+            let mut out_data = ptr::null_mut();
+            let mut out_size: c_int = 0;
+            let consumed = check(av_parser_parse2(
+                self.parser,
+                self.codec_ctx,
+                &mut out_data,
+                &mut out_size,
+                if data.is_empty() {
+                    ptr::null()
+                } else {
+                    data.as_ptr()
+                },
+                data.len() as c_int,
+                pts.unwrap_or(AV_NOPTS_VALUE),
+                dts.unwrap_or(AV_NOPTS_VALUE),
+                0,
+            ))?;
+
+            if out_size <= 0 {
+                return Ok((consumed as usize, None));
+            }
+
+            // the output buffer belongs to the parser
+            let out = std::slice::from_raw_parts(out_data, out_size as usize);
+            let mut packet = Packet::from_bytes(out)?;
+            let parser = &*self.parser;
+            packet.set_pts(ts_to_option(parser.pts));
+            packet.set_dts(ts_to_option(parser.dts));
+            if parser.duration > 0 {
+                packet.set_duration(Some(parser.duration as i64));
+            }
+            packet.set_key(parser.key_frame == 1);
+            if parser.pos >= 0 {
+                packet.set_position(Some(parser.pos));
+            }
+            Ok((consumed as usize, Some(packet)))
+        }
+    }
+
+    /// feed a chunk of the stream and get the packets it completed, pts and
+    /// dts are those of the first byte of data, if known
+    pub fn parse(
+        &mut self,
+        mut data: &[u8],
+        mut pts: Option<i64>,
+        mut dts: Option<i64>,
+    ) -> Result<Vec<Packet>> {
+        let mut packets = Vec::new();
+        while !data.is_empty() {
+            let (consumed, packet) = self.parse_next(data, pts, dts)?;
+            if consumed == 0 && packet.is_none() {
+                break;
+            }
+            packets.extend(packet);
+            data = &data[consumed..];
+            // the timestamps were registered with the first call
+            pts = None;
+            dts = None;
+        }
+        Ok(packets)
+    }
+
+    /// the last buffered packet at the end of stream
+    pub fn flush(&mut self) -> Result<Option<Packet>> {
+        self.parse_next(&[], None, None).map(|(_, packet)| packet)
+    }
+
+    /// the width of the last parsed picture, if known
+    pub fn width(&self) -> Option<u32> {
+        unsafe { positive((*self.parser).width) }
+    }
+
+    pub fn height(&self) -> Option<u32> {
+        unsafe { positive((*self.parser).height) }
+    }
+
+    /// the type of the last parsed picture, PictureType::None if unknown
+    pub fn picture_type(&self) -> PictureType {
+        unsafe { ((*self.parser).pict_type as AVPictureType).into() }
+    }
+
+    /// whether the last parsed packet is a keyframe
+    pub fn is_key(&self) -> bool {
+        unsafe { (*self.parser).key_frame == 1 }
+    }
+}
+
+fn positive(value: c_int) -> Option<u32> {
+    if value > 0 {
+        Some(value as u32)
+    } else {
+        None
+    }
+}
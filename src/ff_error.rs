@@ -1,8 +1,10 @@
 #![allow(unused)]
 
 use crate::*;
-use libc::c_int;
+use libc::{c_char, c_int, EAGAIN, EINVAL};
 use log::error;
+use std::ffi::{CStr, NulError};
+use std::fmt;
 
 /// parts of this file are copied from https://github.com/zmwangx/rust-ffmpeg
 /// credit goes to zmwangx
@@ -44,6 +46,9 @@ pub const AVERROR_PATCHWELCOME: c_int = FFERRTAG!(b'P', b'A', b'W', b'E');
 pub const AVERROR_PROTOCOL_NOT_FOUND: c_int = FFERRTAG!(0xF8, b'P', b'R', b'O');
 
 pub const AVERROR_STREAM_NOT_FOUND: c_int = FFERRTAG!(0xF8, b'S', b'T', b'R');
+/// not an FFmpeg code, av_parser_init() reports no error when there is no
+/// parser for a codec
+pub const AVERROR_PARSER_NOT_FOUND: c_int = FFERRTAG!(0xF8, b'P', b'A', b'R');
 
 pub const AVERROR_BUG2: c_int = FFERRTAG!(b'B', b'U', b'G', b' ');
 pub const AVERROR_UNKNOWN: c_int = FFERRTAG!(b'U', b'N', b'K', b'N');
@@ -54,3 +59,153 @@ pub const AVERROR_HTTP_FORBIDDEN: c_int = FFERRTAG!(0xF8, b'4', b'0', b'3');
 pub const AVERROR_HTTP_NOT_FOUND: c_int = FFERRTAG!(0xF8, b'4', b'0', b'4');
 pub const AVERROR_HTTP_OTHER_4XX: c_int = FFERRTAG!(0xF8, b'4', b'X', b'X');
 pub const AVERROR_HTTP_SERVER_ERROR: c_int = FFERRTAG!(0xF8, b'5', b'X', b'X');
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// errors returned by the libav* functions, the most common codes get their
+/// own variants so callers don't have to compare against the raw AVERROR values
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// AVERROR_EOF, end of file or the codec has been fully drained
+    Eof,
+    /// AVERROR(EAGAIN), output is not available in the current state
+    Again,
+    /// AVERROR_INVALIDDATA
+    InvalidData,
+    /// AVERROR_DECODER_NOT_FOUND
+    DecoderNotFound,
+    /// AVERROR_DEMUXER_NOT_FOUND
+    DemuxerNotFound,
+    /// AVERROR_PARSER_NOT_FOUND, there is no parser for the codec
+    ParserNotFound,
+    /// AVERROR(errno), carries the positive errno value
+    Io(c_int),
+    /// any other negative error code along with its av_strerror text
+    Other { code: c_int, message: String },
+}
+
+impl Error {
+    pub fn from_code(code: c_int) -> Self {
+        match code {
+            AVERROR_EOF => Error::Eof,
+            AVERROR_INVALIDDATA => Error::InvalidData,
+            AVERROR_DECODER_NOT_FOUND => Error::DecoderNotFound,
+            AVERROR_DEMUXER_NOT_FOUND => Error::DemuxerNotFound,
+            AVERROR_PARSER_NOT_FOUND => Error::ParserNotFound,
+            c if c == AVERROR(EAGAIN) => Error::Again,
+            // FFERRTAG codes are far out of the errno range
+            c if c < 0 && c > -4096 => Error::Io(-c),
+            c => Error::Other {
+                code: c,
+                message: strerror(c),
+            },
+        }
+    }
+
+    /// the AVERROR code this error was built from
+    pub fn code(&self) -> c_int {
+        match self {
+            Error::Eof => AVERROR_EOF,
+            Error::Again => AVERROR(EAGAIN),
+            Error::InvalidData => AVERROR_INVALIDDATA,
+            Error::DecoderNotFound => AVERROR_DECODER_NOT_FOUND,
+            Error::DemuxerNotFound => AVERROR_DEMUXER_NOT_FOUND,
+            Error::ParserNotFound => AVERROR_PARSER_NOT_FOUND,
+            Error::Io(errno) => AVERROR(*errno),
+            Error::Other { code, .. } => *code,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Other { message, .. } => f.write_str(message),
+            // unknown to av_strerror
+            Error::ParserNotFound => f.write_str("Parser not found"),
+            _ => f.write_str(&strerror(self.code())),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<NulError> for Error {
+    fn from(_: NulError) -> Self {
+        Error::Io(EINVAL)
+    }
+}
+
+fn strerror(errnum: c_int) -> String {
+    unsafe {
+        let mut buf = [0 as c_char; 1024];
+        av_strerror(errnum, buf.as_mut_ptr(), buf.len() as u64);
+        CStr::from_ptr(buf.as_ptr()).to_string_lossy().into_owned()
+    }
+}
+
+/// turns the return value of a libav* function into a Result, negative values
+/// are errors and non-negative values are passed through
+#[inline]
+pub(crate) fn check(ret: c_int) -> Result<c_int> {
+    if ret < 0 {
+        Err(Error::from_code(ret))
+    } else {
+        Ok(ret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libc::{EIO, ENOMEM};
+
+    #[test]
+    fn from_code_maps_common_codes_to_variants() {
+        assert_eq!(Error::from_code(AVERROR_EOF), Error::Eof);
+        assert_eq!(Error::from_code(AVERROR(EAGAIN)), Error::Again);
+        assert_eq!(Error::from_code(AVERROR_INVALIDDATA), Error::InvalidData);
+        assert_eq!(
+            Error::from_code(AVERROR_DECODER_NOT_FOUND),
+            Error::DecoderNotFound
+        );
+        assert_eq!(
+            Error::from_code(AVERROR_DEMUXER_NOT_FOUND),
+            Error::DemuxerNotFound
+        );
+        assert_eq!(Error::from_code(AVERROR(ENOMEM)), Error::Io(ENOMEM));
+    }
+
+    #[test]
+    fn code_round_trips() {
+        let errors = [
+            Error::Eof,
+            Error::Again,
+            Error::InvalidData,
+            Error::DecoderNotFound,
+            Error::DemuxerNotFound,
+            Error::ParserNotFound,
+            Error::Io(EINVAL),
+            Error::Io(EIO),
+        ];
+        for e in errors.iter() {
+            assert_eq!(&Error::from_code(e.code()), e);
+        }
+    }
+
+    #[test]
+    fn other_keeps_its_code() {
+        let e = Error::Other {
+            code: AVERROR_BUG,
+            message: String::new(),
+        };
+        assert_eq!(e.code(), AVERROR_BUG);
+    }
+
+    #[test]
+    fn check_passes_non_negative_values() {
+        assert_eq!(check(0), Ok(0));
+        assert_eq!(check(42), Ok(42));
+        assert_eq!(check(AVERROR_EOF), Err(Error::Eof));
+    }
+}
@@ -0,0 +1,99 @@
+use crate::*;
+use libc::{c_char, c_int, c_void};
+use log::{Level, LevelFilter};
+use std::ffi::CStr;
+
+// va_list decays to a pointer to __va_list_tag in the bindings generated
+// for x86_64, other targets keep the va_list alias
+#[cfg(all(target_arch = "x86_64", not(target_os = "windows")))]
+type VaList = *mut __va_list_tag;
+#[cfg(not(all(target_arch = "x86_64", not(target_os = "windows"))))]
+type VaList = va_list;
+
+const DEFAULT_TARGET: &str = "ffmpeg";
+
+/// route av_log output into the `log` facade, messages more verbose than
+/// `max_level` are dropped by libav before they get formatted
+pub(crate) fn redirect_av_log(max_level: LevelFilter) {
+    let av_level = match max_level {
+        LevelFilter::Off => AV_LOG_QUIET,
+        LevelFilter::Error => AV_LOG_ERROR as c_int,
+        LevelFilter::Warn => AV_LOG_WARNING as c_int,
+        LevelFilter::Info => AV_LOG_INFO as c_int,
+        LevelFilter::Debug => AV_LOG_DEBUG as c_int,
+        LevelFilter::Trace => AV_LOG_TRACE as c_int,
+    };
+
+    unsafe {
+        av_log_set_level(av_level);
+        av_log_set_callback(Some(av_log_callback));
+    }
+}
+
+fn to_log_level(av_level: c_int) -> Level {
+    match av_level {
+        l if l <= AV_LOG_ERROR as c_int => Level::Error,
+        l if l <= AV_LOG_WARNING as c_int => Level::Warn,
+        l if l <= AV_LOG_INFO as c_int => Level::Info,
+        l if l <= AV_LOG_DEBUG as c_int => Level::Debug,
+        _ => Level::Trace,
+    }
+}
+
+/// the AVClass item name of the logging context, i.e. "h264" or "mov,mp4,m4a,3gp,3g2,mj2"
+unsafe fn item_name<'a>(avcl: *mut c_void) -> Option<&'a str> {
+    if avcl.is_null() {
+        return None;
+    }
+
+    // every struct passed to av_log starts with a pointer to its AVClass
+    let class = *(avcl as *const *const AVClass);
+    if class.is_null() {
+        return None;
+    }
+
+    let name = match (*class).item_name {
+        Some(item_name) => item_name(avcl),
+        None => (*class).class_name,
+    };
+    if name.is_null() {
+        return None;
+    }
+    CStr::from_ptr(name).to_str().ok()
+}
+
+unsafe extern "C" fn av_log_callback(
+    avcl: *mut c_void,
+    av_level: c_int,
+    fmt: *const c_char,
+    vl: VaList,
+) {
+    if av_level > av_log_get_level() {
+        return;
+    }
+
+    let level = to_log_level(av_level);
+    let target = item_name(avcl).unwrap_or(DEFAULT_TARGET);
+    if !log::log_enabled!(target: target, level) {
+        return;
+    }
+
+    // the "[h264 @ 0x...]" prefix is left out, the item name is used as the target instead
+    let mut print_prefix = 0;
+    let mut buf = [0 as c_char; 1024];
+    av_log_format_line2(
+        avcl,
+        av_level,
+        fmt,
+        vl,
+        buf.as_mut_ptr(),
+        buf.len() as c_int,
+        &mut print_prefix,
+    );
+
+    let line = CStr::from_ptr(buf.as_ptr()).to_string_lossy();
+    let line = line.trim_end();
+    if !line.is_empty() {
+        log::log!(target: target, level, "{}", line);
+    }
+}
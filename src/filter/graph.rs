@@ -0,0 +1,409 @@
+use crate::ff_error::*;
+use crate::*;
+use libc::{c_char, c_int, EINVAL, ENOMEM};
+use log::error;
+use std::ffi::{CStr, CString};
+use std::ptr;
+
+/// a filter graph described in the syntax of the ffmpeg CLI, i.e.
+/// "scale=640:-2,fps=30,format=yuv420p" or "[in0][in1]overlay=10:10[out]".
+///
+/// the inputs and outputs are added first, their names are the labels of the
+/// description. libavfilter links the unlabeled first input and last output
+/// pads of the description to the labels "in" and "out", so a single input
+/// or output whose name doesn't appear in the description is linked to them,
+/// whatever its name. parse() then links and configures the graph, frames
+/// can be pushed and pulled after that.
+pub struct Graph {
+    graph: *mut AVFilterGraph,
+    // buffer/abuffer sources and buffersink/abuffersink sinks by name
+    inputs: Vec<(String, *mut AVFilterContext)>,
+    outputs: Vec<(String, *mut AVFilterContext)>,
+    configured: bool,
+}
+
+impl Drop for Graph {
+    fn drop(&mut self) {
+        unsafe { avfilter_graph_free(&mut self.graph) }
+    }
+}
+
+impl Graph {
+    pub fn new() -> Result<Self> {
+        let graph = unsafe { avfilter_graph_alloc() };
+        if graph.is_null() {
+            return Err(Error::Io(ENOMEM));
+        }
+        Ok(Graph {
+            graph,
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            configured: false,
+        })
+    }
+
+    /// a "buffer" source for video frames of the given properties
+    pub fn add_video_input(
+        &mut self,
+        name: &str,
+        width: u32,
+        height: u32,
+        pix_fmt: PixelFormat,
+        time_base: Rational,
+        sample_aspect_ratio: Rational,
+    ) -> Result<()> {
+        let sar = if sample_aspect_ratio.is_valid() {
+            sample_aspect_ratio
+        } else {
+            Rational::new(1, 1)
+        };
+        let args = format!(
+            "video_size={}x{}:pix_fmt={}:time_base={}:pixel_aspect={}",
+            width,
+            height,
+            AVPixelFormat::from(pix_fmt),
+            time_base,
+            sar
+        );
+        let ctx = self.create_filter("buffer", name, &args)?;
+        self.inputs.push((name.to_owned(), ctx));
+        Ok(())
+    }
+
+    /// an "abuffer" source for audio frames of the given properties
+    pub fn add_audio_input(
+        &mut self,
+        name: &str,
+        sample_rate: u32,
+        sample_fmt: SampleFormat,
+        channel_layout: u64,
+        time_base: Rational,
+    ) -> Result<()> {
+        let args = format!(
+            "time_base={}:sample_rate={}:sample_fmt={}:channel_layout={:#x}",
+            time_base,
+            sample_rate,
+            sample_fmt.name().unwrap_or("none"),
+            channel_layout
+        );
+        let ctx = self.create_filter("abuffer", name, &args)?;
+        self.inputs.push((name.to_owned(), ctx));
+        Ok(())
+    }
+
+    /// a source for the frames output by decoder, video or audio
+    pub fn add_input_from_decoder(&mut self, name: &str, decoder: &Decoder) -> Result<()> {
+        let time_base = if decoder.pkt_timebase.num != 0 {
+            decoder.pkt_timebase
+        } else {
+            decoder.time_base
+        };
+        match MediaType::from(decoder.codec_type) {
+            MediaType::Video => self.add_video_input(
+                name,
+                decoder.width as u32,
+                decoder.height as u32,
+                decoder.pix_fmt.into(),
+                time_base.into(),
+                decoder.sample_aspect_ratio.into(),
+            ),
+            MediaType::Audio => {
+                let channel_layout = match decoder.channel_layout {
+                    0 => unsafe { av_get_default_channel_layout(decoder.channels) as u64 },
+                    layout => layout,
+                };
+                self.add_audio_input(
+                    name,
+                    decoder.sample_rate as u32,
+                    decoder.sample_fmt.into(),
+                    channel_layout,
+                    time_base.into(),
+                )
+            }
+            media_type => {
+                error!("can't filter {:?} frames", media_type);
+                Err(Error::Io(EINVAL))
+            }
+        }
+    }
+
+    pub fn add_video_output(&mut self, name: &str) -> Result<()> {
+        let ctx = self.create_filter("buffersink", name, "")?;
+        self.outputs.push((name.to_owned(), ctx));
+        Ok(())
+    }
+
+    pub fn add_audio_output(&mut self, name: &str) -> Result<()> {
+        let ctx = self.create_filter("abuffersink", name, "")?;
+        self.outputs.push((name.to_owned(), ctx));
+        Ok(())
+    }
+
+    /// link the inputs and outputs with the filters of description and
+    /// configure the graph, fails with Error::Io(EINVAL) if an input, an
+    /// output or a pad of the description is left unlinked
+    pub fn parse(&mut self, description: &str) -> Result<()> {
+        let c_description = CString::new(description)?;
+        unsafe {
+            // the open outputs of the graph are the outputs of its sources
+            let mut open_outputs = inout_list(&self.inputs, "in", description)?;
+            let mut open_inputs = match inout_list(&self.outputs, "out", description) {
+                Ok(list) => list,
+                Err(e) => {
+                    avfilter_inout_free(&mut open_outputs);
+                    return Err(e);
+                }
+            };
+
+            let ret = avfilter_graph_parse_ptr(
+                self.graph,
+                c_description.as_ptr(),
+                &mut open_inputs,
+                &mut open_outputs,
+                ptr::null_mut(),
+            );
+            // the inputs and outputs that were not linked are left in the lists
+            let unlinked: Vec<String> = inout_names(open_outputs)
+                .into_iter()
+                .chain(inout_names(open_inputs))
+                .collect();
+            avfilter_inout_free(&mut open_inputs);
+            avfilter_inout_free(&mut open_outputs);
+            if let Err(e) = check(ret) {
+                error!("failed to parse filter graph \"{}\": {}", description, e);
+                return Err(e);
+            }
+            if !unlinked.is_empty() {
+                error!(
+                    "unlinked inputs or outputs in filter graph \"{}\": {}",
+                    description,
+                    unlinked.join(", ")
+                );
+                return Err(Error::Io(EINVAL));
+            }
+
+            check(avfilter_graph_config(self.graph, ptr::null_mut()))?;
+        }
+        self.configured = true;
+        Ok(())
+    }
+
+    /// the frame is referenced, not consumed
+    pub fn push(&mut self, input: &str, frame: &Frame) -> Result<()> {
+        let ctx = self.input_ctx(input)?;
+        unsafe {
+            check(av_buffersrc_add_frame_flags(
+                ctx,
+                frame.as_ptr() as *mut AVFrame,
+                AV_BUFFERSRC_FLAG_KEEP_REF as c_int,
+            ))
+            .map(|_| ())
+        }
+    }
+
+    /// signal the end of stream on input, the buffered frames can then be
+    /// pulled until pull() fails with Error::Eof
+    pub fn push_eof(&mut self, input: &str) -> Result<()> {
+        let ctx = self.input_ctx(input)?;
+        unsafe { check(av_buffersrc_add_frame(ctx, ptr::null_mut())).map(|_| ()) }
+    }
+
+    /// fails with Error::Again if more frames must be pushed, Error::Eof if
+    /// all inputs have ended and there will be no more output frames
+    pub fn pull(&mut self, output: &str, out_frame: &mut Frame) -> Result<()> {
+        let ctx = self.output_ctx(output)?;
+        out_frame.unref();
+        unsafe { check(av_buffersink_get_frame(ctx, out_frame.as_mut_ptr())).map(|_| ()) }
+    }
+
+    /// the time base of the frames pulled from output
+    pub fn output_time_base(&self, output: &str) -> Result<Rational> {
+        let ctx = self.output_ctx(output)?;
+        Ok(unsafe { av_buffersink_get_time_base(ctx) }.into())
+    }
+
+    /// 0/1 if unknown or variable
+    pub fn output_frame_rate(&self, output: &str) -> Result<Rational> {
+        let ctx = self.output_ctx(output)?;
+        Ok(unsafe { av_buffersink_get_frame_rate(ctx) }.into())
+    }
+
+    /// the size of the video frames pulled from output
+    pub fn output_size(&self, output: &str) -> Result<(u32, u32)> {
+        let ctx = self.output_ctx(output)?;
+        unsafe {
+            Ok((
+                av_buffersink_get_w(ctx).max(0) as u32,
+                av_buffersink_get_h(ctx).max(0) as u32,
+            ))
+        }
+    }
+
+    /// the sample aspect ratio of the video frames pulled from output, 0/1 if
+    /// unknown
+    pub fn output_sample_aspect_ratio(&self, output: &str) -> Result<Rational> {
+        let ctx = self.output_ctx(output)?;
+        Ok(unsafe { av_buffersink_get_sample_aspect_ratio(ctx) }.into())
+    }
+
+    pub fn output_pixel_format(&self, output: &str) -> Result<PixelFormat> {
+        let ctx = self.output_ctx(output)?;
+        Ok(unsafe { av_buffersink_get_format(ctx) }.into())
+    }
+
+    pub fn output_sample_format(&self, output: &str) -> Result<SampleFormat> {
+        let ctx = self.output_ctx(output)?;
+        Ok(unsafe { av_buffersink_get_format(ctx) }.into())
+    }
+
+    pub fn output_sample_rate(&self, output: &str) -> Result<u32> {
+        let ctx = self.output_ctx(output)?;
+        Ok(unsafe { av_buffersink_get_sample_rate(ctx) }.max(0) as u32)
+    }
+
+    /// AV_CH_LAYOUT_* bitmask of the audio frames pulled from output
+    pub fn output_channel_layout(&self, output: &str) -> Result<u64> {
+        let ctx = self.output_ctx(output)?;
+        unsafe {
+            Ok(match av_buffersink_get_channel_layout(ctx) {
+                0 => av_get_default_channel_layout(av_buffersink_get_channels(ctx)) as u64,
+                layout => layout,
+            })
+        }
+    }
+
+    /// change a parameter of a running filter, i.e. ("volume", "volume", "0.5")
+    /// or ("all", "enable", "0"), returns the response of the filter
+    pub fn send_command(&mut self, target: &str, cmd: &str, arg: &str) -> Result<String> {
+        let c_target = CString::new(target)?;
+        let c_cmd = CString::new(cmd)?;
+        let c_arg = CString::new(arg)?;
+        let mut res = [0 as c_char; 256];
+        unsafe {
+            check(avfilter_graph_send_command(
+                self.graph,
+                c_target.as_ptr(),
+                c_cmd.as_ptr(),
+                c_arg.as_ptr(),
+                res.as_mut_ptr(),
+                res.len() as c_int,
+                0,
+            ))?;
+            Ok(CStr::from_ptr(res.as_ptr()).to_string_lossy().into_owned())
+        }
+    }
+
+    fn create_filter(
+        &mut self,
+        filter: &str,
+        name: &str,
+        args: &str,
+    ) -> Result<*mut AVFilterContext> {
+        if self.configured {
+            error!("filter graph is already configured");
+            return Err(Error::Io(EINVAL));
+        }
+
+        let c_filter = CString::new(filter)?;
+        let c_name = CString::new(name)?;
+        let c_args = CString::new(args)?;
+        unsafe {
+            let filter = avfilter_get_by_name(c_filter.as_ptr());
+            if filter.is_null() {
+                return Err(Error::from_code(AVERROR_FILTER_NOT_FOUND));
+            }
+
+            let mut ctx = ptr::null_mut();
+            check(avfilter_graph_create_filter(
+                &mut ctx,
+                filter,
+                c_name.as_ptr(),
+                if args.is_empty() {
+                    ptr::null()
+                } else {
+                    c_args.as_ptr()
+                },
+                ptr::null_mut(),
+                self.graph,
+            ))?;
+            Ok(ctx)
+        }
+    }
+
+    fn input_ctx(&self, name: &str) -> Result<*mut AVFilterContext> {
+        self.find_ctx(&self.inputs, name)
+    }
+
+    fn output_ctx(&self, name: &str) -> Result<*mut AVFilterContext> {
+        self.find_ctx(&self.outputs, name)
+    }
+
+    fn find_ctx(
+        &self,
+        list: &[(String, *mut AVFilterContext)],
+        name: &str,
+    ) -> Result<*mut AVFilterContext> {
+        if !self.configured {
+            error!("filter graph must be parsed first");
+            return Err(Error::Io(EINVAL));
+        }
+        match list.iter().find(|(n, _)| n == name) {
+            Some((_, ctx)) => Ok(*ctx),
+            None => {
+                error!("filter graph has no input or output named \"{}\"", name);
+                Err(Error::Io(EINVAL))
+            }
+        }
+    }
+}
+
+// a linked list of AVFilterInOut for the filters, freed with avfilter_inout_free.
+// a single filter not labeled in description gets default_label instead of its
+// name, the label libavfilter gives the unlabeled pad at that end.
+unsafe fn inout_list(
+    filters: &[(String, *mut AVFilterContext)],
+    default_label: &str,
+    description: &str,
+) -> Result<*mut AVFilterInOut> {
+    let mut head: *mut AVFilterInOut = ptr::null_mut();
+    for (name, ctx) in filters.iter().rev() {
+        let label = if filters.len() == 1 && !description.contains(&format!("[{}]", name)) {
+            default_label
+        } else {
+            name.as_str()
+        };
+        let c_name = match CString::new(label) {
+            Ok(c_name) => c_name,
+            Err(e) => {
+                avfilter_inout_free(&mut head);
+                return Err(e.into());
+            }
+        };
+        let inout = avfilter_inout_alloc();
+        if inout.is_null() {
+            avfilter_inout_free(&mut head);
+            return Err(Error::Io(ENOMEM));
+        }
+        (*inout).name = av_strdup(c_name.as_ptr());
+        (*inout).filter_ctx = *ctx;
+        (*inout).pad_idx = 0;
+        (*inout).next = head;
+        head = inout;
+    }
+    Ok(head)
+}
+
+// the labels of the entries of an AVFilterInOut list, "?" for unlabeled pads
+unsafe fn inout_names(mut inout: *const AVFilterInOut) -> Vec<String> {
+    let mut names = Vec::new();
+    while !inout.is_null() {
+        let name = (*inout).name;
+        names.push(if name.is_null() {
+            "?".to_owned()
+        } else {
+            CStr::from_ptr(name).to_string_lossy().into_owned()
+        });
+        inout = (*inout).next;
+    }
+    names
+}
@@ -0,0 +1,38 @@
+use crate::*;
+
+/// an embedded picture such as album art (ID3 APIC, mp4 covr), stored as a
+/// single packet in a video stream with the ATTACHED_PIC disposition
+#[derive(Debug, Clone)]
+pub struct AttachedPicture {
+    pub stream_index: usize,
+    /// usually CodecId::Mjpeg or CodecId::Png
+    pub codec_id: CodecId,
+    /// the encoded image, i.e. the content of a .jpg or .png file
+    pub data: Vec<u8>,
+    /// the "comment" tag, i.e. "Cover (front)"
+    pub comment: Option<String>,
+}
+
+impl AttachedPicture {
+    /// i.e. "image/jpeg"
+    pub fn mime_type(&self) -> Option<&'static str> {
+        match self.codec_id {
+            CodecId::Mjpeg => Some("image/jpeg"),
+            CodecId::Png => Some("image/png"),
+            CodecId::Bmp => Some("image/bmp"),
+            CodecId::Gif => Some("image/gif"),
+            CodecId::Webp => Some("image/webp"),
+            _ => None,
+        }
+    }
+}
+
+/// a file attached to the container, i.e. a font in a Matroska file
+#[derive(Debug, Clone)]
+pub struct Attachment {
+    pub stream_index: usize,
+    pub filename: Option<String>,
+    /// i.e. "application/x-truetype-font"
+    pub mime_type: Option<String>,
+    pub data: Vec<u8>,
+}
@@ -0,0 +1,135 @@
+use crate::ff_error::*;
+use crate::*;
+use libc::{c_int, c_void, EIO, ENOMEM, SEEK_CUR, SEEK_END, SEEK_SET};
+use std::io::{self, Read, Seek, SeekFrom};
+
+const IO_BUFFER_SIZE: usize = 32 * 1024;
+
+pub trait ReadSeek: Read + Seek + Send {}
+impl<T: Read + Seek + Send> ReadSeek for T {}
+
+enum Source {
+    Seekable(Box<dyn ReadSeek>),
+    Unseekable(Box<dyn Read + Send>),
+}
+
+impl Source {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Source::Seekable(r) => r.read(buf),
+            Source::Unseekable(r) => r.read(buf),
+        }
+    }
+}
+
+/// an AVIOContext that pulls its data from a Rust reader, it must outlive
+/// the AVFormatContext it is attached to
+pub(crate) struct IoContext {
+    ctx: *mut AVIOContext,
+    source: *mut Source,
+}
+
+impl Drop for IoContext {
+    fn drop(&mut self) {
+        unsafe {
+            if !self.ctx.is_null() {
+                // the buffer may have been reallocated by libavformat, so free
+                // whatever the context currently points to
+                av_freep(&mut (*self.ctx).buffer as *mut *mut u8 as *mut c_void);
+                avio_context_free(&mut self.ctx);
+            }
+            drop(Box::from_raw(self.source));
+        }
+    }
+}
+
+impl IoContext {
+    pub fn with_reader<R: Read + Seek + Send + 'static>(reader: R) -> Result<Self> {
+        Self::new(Source::Seekable(Box::new(reader)))
+    }
+
+    pub fn with_unseekable_reader<R: Read + Send + 'static>(reader: R) -> Result<Self> {
+        Self::new(Source::Unseekable(Box::new(reader)))
+    }
+
+    fn new(source: Source) -> Result<Self> {
+        let seekable = matches!(source, Source::Seekable(_));
+        let source = Box::into_raw(Box::new(source));
+
+        unsafe {
+            let buffer = av_malloc(IO_BUFFER_SIZE as u64) as *mut u8;
+            if buffer.is_null() {
+                drop(Box::from_raw(source));
+                return Err(Error::Io(ENOMEM));
+            }
+
+            let ctx = avio_alloc_context(
+                buffer,
+                IO_BUFFER_SIZE as c_int,
+                0,
+                source as *mut c_void,
+                Some(read_packet),
+                None,
+                if seekable { Some(seek) } else { None },
+            );
+            if ctx.is_null() {
+                av_free(buffer as *mut c_void);
+                drop(Box::from_raw(source));
+                return Err(Error::Io(ENOMEM));
+            }
+
+            Ok(IoContext { ctx, source })
+        }
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut AVIOContext {
+        self.ctx
+    }
+}
+
+fn io_error_code(e: &io::Error) -> c_int {
+    AVERROR(e.raw_os_error().unwrap_or(EIO))
+}
+
+unsafe extern "C" fn read_packet(opaque: *mut c_void, buf: *mut u8, buf_size: c_int) -> c_int {
+    let source = &mut *(opaque as *mut Source);
+    let buf = std::slice::from_raw_parts_mut(buf, buf_size as usize);
+    loop {
+        match source.read(buf) {
+            Ok(0) => return AVERROR_EOF,
+            Ok(n) => return n as c_int,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return io_error_code(&e),
+        }
+    }
+}
+
+unsafe extern "C" fn seek(opaque: *mut c_void, offset: i64, whence: c_int) -> i64 {
+    let reader = match &mut *(opaque as *mut Source) {
+        Source::Seekable(r) => r,
+        Source::Unseekable(_) => return AVERROR(EIO) as i64,
+    };
+
+    let whence = whence & !(AVSEEK_FORCE as c_int);
+    let pos = match whence {
+        w if w == AVSEEK_SIZE as c_int => stream_len(reader),
+        SEEK_SET => reader.seek(SeekFrom::Start(offset as u64)),
+        SEEK_CUR => reader.seek(SeekFrom::Current(offset)),
+        SEEK_END => reader.seek(SeekFrom::End(offset)),
+        _ => return AVERROR(EIO) as i64,
+    };
+
+    match pos {
+        Ok(pos) => pos as i64,
+        Err(e) => io_error_code(&e) as i64,
+    }
+}
+
+fn stream_len(reader: &mut Box<dyn ReadSeek>) -> io::Result<u64> {
+    let cur = reader.stream_position()?;
+    let len = reader.seek(SeekFrom::End(0))?;
+    if cur != len {
+        reader.seek(SeekFrom::Start(cur))?;
+    }
+    Ok(len)
+}
@@ -0,0 +1,28 @@
+use crate::*;
+
+#[derive(Debug, Clone)]
+pub struct Chapter {
+    /// format specific chapter id
+    pub id: i64,
+    /// in seconds
+    pub start: f64,
+    /// in seconds
+    pub end: f64,
+    /// the "title" tag, if any
+    pub title: Option<String>,
+    pub metadata: Dictionary,
+}
+
+impl Chapter {
+    pub(crate) unsafe fn from_raw(chapter: &AVChapter) -> Self {
+        let time_base = Rational::from(chapter.time_base);
+        let metadata = Dictionary::copy_from(chapter.metadata);
+        Chapter {
+            id: chapter.id as i64,
+            start: chapter.start as f64 * time_base.as_f64(),
+            end: chapter.end as f64 * time_base.as_f64(),
+            title: metadata.get("title").map(str::to_owned),
+            metadata,
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use crate::*;
+use libc::c_int;
+
+/// the AV_DISPOSITION_* flags of a stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Disposition(c_int);
+
+impl Disposition {
+    pub const DEFAULT: Disposition = Disposition(AV_DISPOSITION_DEFAULT as c_int);
+    pub const DUB: Disposition = Disposition(AV_DISPOSITION_DUB as c_int);
+    pub const ORIGINAL: Disposition = Disposition(AV_DISPOSITION_ORIGINAL as c_int);
+    pub const COMMENT: Disposition = Disposition(AV_DISPOSITION_COMMENT as c_int);
+    pub const LYRICS: Disposition = Disposition(AV_DISPOSITION_LYRICS as c_int);
+    pub const KARAOKE: Disposition = Disposition(AV_DISPOSITION_KARAOKE as c_int);
+    pub const FORCED: Disposition = Disposition(AV_DISPOSITION_FORCED as c_int);
+    pub const HEARING_IMPAIRED: Disposition = Disposition(AV_DISPOSITION_HEARING_IMPAIRED as c_int);
+    pub const VISUAL_IMPAIRED: Disposition = Disposition(AV_DISPOSITION_VISUAL_IMPAIRED as c_int);
+    pub const CLEAN_EFFECTS: Disposition = Disposition(AV_DISPOSITION_CLEAN_EFFECTS as c_int);
+    pub const ATTACHED_PIC: Disposition = Disposition(AV_DISPOSITION_ATTACHED_PIC as c_int);
+    pub const TIMED_THUMBNAILS: Disposition = Disposition(AV_DISPOSITION_TIMED_THUMBNAILS as c_int);
+    pub const CAPTIONS: Disposition = Disposition(AV_DISPOSITION_CAPTIONS as c_int);
+    pub const DESCRIPTIONS: Disposition = Disposition(AV_DISPOSITION_DESCRIPTIONS as c_int);
+    pub const METADATA: Disposition = Disposition(AV_DISPOSITION_METADATA as c_int);
+
+    pub const fn from_bits(bits: c_int) -> Self {
+        Disposition(bits)
+    }
+
+    pub const fn bits(&self) -> c_int {
+        self.0
+    }
+
+    pub const fn contains(&self, other: Disposition) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+}
@@ -1,20 +1,23 @@
+use super::avio::IoContext;
 use crate::ff_error::*;
 use crate::*;
-use libc::c_char;
-use log::error;
-use std::ffi::CStr;
+use libc::{c_int, ENOMEM};
+use std::io::{Read, Seek};
 use std::ops::Deref;
 use std::{ffi::CString, ptr::null_mut};
 
 pub struct Input {
     fmt: *mut AVFormatContext,
+    // custom I/O, dropped after fmt is closed
+    _io: Option<IoContext>,
+    unused_options: Dictionary,
 }
 
 impl Drop for Input {
     fn drop(&mut self) {
         if !self.fmt.is_null() {
             unsafe {
-                avformat_free_context(self.fmt);
+                avformat_close_input(&mut self.fmt);
             }
         }
     }
@@ -29,50 +32,336 @@ impl Deref for Input {
 }
 
 impl Input {
-    pub fn with_url(url: &str) -> Option<Self> {
+    pub fn with_url(url: &str) -> Result<Self> {
+        InputBuilder::new().open_url(url)
+    }
+
+    /// demux from any seekable reader, i.e. a File or an object-store client
+    pub fn from_reader<R: Read + Seek + Send + 'static>(reader: R) -> Result<Self> {
+        InputBuilder::new().open_reader(reader)
+    }
+
+    /// demux from a reader that can't seek, formats that need seeking to
+    /// locate their index (i.e. mp4 with the moov atom at the end) may fail
+    pub fn from_unseekable_reader<R: Read + Send + 'static>(reader: R) -> Result<Self> {
+        InputBuilder::new().open_unseekable_reader(reader)
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self> {
+        InputBuilder::new().open_bytes(bytes)
+    }
+
+    pub fn builder() -> InputBuilder {
+        InputBuilder::new()
+    }
+
+    pub(super) fn open(
+        c_url: &CString,
+        mut io: Option<IoContext>,
+        format: *mut AVInputFormat,
+        options: &mut *mut AVDictionary,
+        find_stream_info: bool,
+    ) -> Result<Self> {
         unsafe {
             let mut fmt = avformat_alloc_context();
-            let c_url = CString::new(url).unwrap();
-            let ret = avformat_open_input(&mut fmt, c_url.as_ptr(), null_mut(), null_mut());
-            if ret < 0 {
-                ff_error!(ret, "avformat_open_input failed");
-                return None;
+            if fmt.is_null() {
+                return Err(Error::Io(ENOMEM));
+            }
+
+            if let Some(io) = io.as_mut() {
+                (*fmt).pb = io.as_mut_ptr();
+                (*fmt).flags |= AVFMT_FLAG_CUSTOM_IO as i32;
+            }
+
+            // avformat_open_input frees the context on failure
+            check(avformat_open_input(
+                &mut fmt,
+                c_url.as_ptr(),
+                format,
+                options,
+            ))?;
+
+            if find_stream_info {
+                if let Err(e) = check(avformat_find_stream_info(fmt, null_mut())) {
+                    avformat_close_input(&mut fmt);
+                    return Err(e);
+                }
+            }
+
+            Ok(Input {
+                fmt,
+                _io: io,
+                unused_options: Dictionary::new(),
+            })
+        }
+    }
+
+    /// the options passed to InputBuilder that were not recognized by the demuxer
+    pub fn unused_options(&self) -> &Dictionary {
+        &self.unused_options
+    }
+
+    pub(super) fn set_unused_options(&mut self, unused_options: Dictionary) {
+        self.unused_options = unused_options;
+    }
+
+    /// iterate over the packets of all streams until end of file, the packets
+    /// are read into a single buffer packet and moved out of it
+    pub fn packets(&mut self) -> Packets<'_> {
+        Packets::new(self, None)
+    }
+
+    /// iterate over the packets of the stream at index until end of file,
+    /// packets of other streams are read and dropped
+    pub fn stream_packets(&mut self, index: usize) -> Packets<'_> {
+        Packets::new(self, Some(index))
+    }
+
+    /// fails with Error::Eof on end of file
+    pub fn read_packet(&self, out_packet: &mut Packet) -> Result<()> {
+        unsafe { check(av_read_frame(self.fmt, out_packet.as_mut_ptr())).map(|_| ()) }
+    }
+
+    /// seek to timestamp, which is in the time base of the stream at stream_index,
+    /// or in AV_TIME_BASE units if stream_index is None. for SeekMode::Byte it is
+    /// a byte position and for SeekMode::Frame a frame number. decoders of the
+    /// input must be flushed after seeking.
+    pub fn seek(
+        &mut self,
+        stream_index: Option<usize>,
+        timestamp: i64,
+        mode: SeekMode,
+    ) -> Result<()> {
+        let stream_index = match stream_index {
+            Some(index) if index >= self.get_stream_count() => {
+                return Err(Error::from_code(AVERROR_STREAM_NOT_FOUND));
+            }
+            Some(index) => index as c_int,
+            None => -1,
+        };
+
+        let (min_ts, max_ts, flags) = match mode {
+            SeekMode::Keyframe => (i64::MIN, timestamp, 0),
+            SeekMode::Any => (i64::MIN, timestamp, AVSEEK_FLAG_ANY),
+            SeekMode::Byte => (i64::MIN, i64::MAX, AVSEEK_FLAG_BYTE),
+            SeekMode::Frame => (i64::MIN, timestamp, AVSEEK_FLAG_FRAME),
+        };
+
+        unsafe {
+            check(avformat_seek_file(
+                self.fmt,
+                stream_index,
+                min_ts,
+                timestamp,
+                max_ts,
+                flags as c_int,
+            ))
+            .map(|_| ())
+        }
+    }
+
+    /// seek to the keyframe before timestamp and decode until the frame that is
+    /// displayed at timestamp, which is returned. timestamp is in the time base
+    /// of the stream, decoder must have been created for the same stream.
+    /// returns None if the stream ends before timestamp.
+    pub fn seek_precise(
+        &mut self,
+        decoder: &mut Decoder,
+        stream_index: usize,
+        timestamp: i64,
+    ) -> Result<Option<Frame>> {
+        self.seek(Some(stream_index), timestamp, SeekMode::Keyframe)?;
+        decoder.flush();
+
+        let reaches_target = |frame: &Frame| {
+            let pts = frame.best_effort_timestamp;
+            pts != AV_NOPTS_VALUE
+                && (pts >= timestamp
+                    || (frame.pkt_duration > 0 && pts + frame.pkt_duration > timestamp))
+        };
+
+        for item in self.stream_packets(stream_index) {
+            let (_, packet) = item?;
+            for frame in decoder.decode(&packet) {
+                let frame = frame?;
+                if reaches_target(&frame) {
+                    return Ok(Some(frame));
+                }
             }
+        }
 
-            let ret = avformat_find_stream_info(fmt, null_mut());
-            if ret < 0 {
-                ff_error!(ret, "avformat_find_stream_info failed");
-                return None;
+        for frame in decoder.drain() {
+            let frame = frame?;
+            if reaches_target(&frame) {
+                return Ok(Some(frame));
             }
+        }
+        Ok(None)
+    }
+
+    /// a copy of the container tags, i.e. "title", "artist", "encoder"
+    pub fn metadata(&self) -> Dictionary {
+        unsafe { Dictionary::copy_from((*self.fmt).metadata) }
+    }
 
-            Some(Input { fmt })
+    pub fn chapters(&self) -> Vec<Chapter> {
+        unsafe {
+            let fmt = &*self.fmt;
+            (0..fmt.nb_chapters as usize)
+                .map(|i| Chapter::from_raw(&**fmt.chapters.add(i)))
+                .collect()
+        }
+    }
+
+    /// the programs of multi-program inputs such as MPEG-TS, empty for most formats
+    pub fn programs(&self) -> Vec<Program> {
+        unsafe {
+            let fmt = &*self.fmt;
+            (0..fmt.nb_programs as usize)
+                .map(|i| Program::from_raw(&**fmt.programs.add(i)))
+                .collect()
         }
     }
 
-    // return 0 on success, AVERROR_EOF on end of file or other negative values are errors
-    pub fn read_packet(&self, out_packet: *mut AVPacket) -> i32 {
+    /// embedded pictures such as album art
+    pub fn attached_pictures(&self) -> Vec<AttachedPicture> {
+        self.streams()
+            .filter_map(|st| st.attached_picture())
+            .collect()
+    }
+
+    /// attached files such as the fonts of Matroska files
+    pub fn attachments(&self) -> Vec<Attachment> {
+        self.streams().filter_map(|st| st.attachment()).collect()
+    }
+
+    /// duration of the input in seconds, if known
+    pub fn duration(&self) -> Option<f64> {
         unsafe {
-            let ret = av_read_frame(self.fmt, out_packet);
-            if ret < 0 && ret == AVERROR_EOF {
-                ff_error!(ret, "av_read_frame failed");
+            match (*self.fmt).duration {
+                AV_NOPTS_VALUE => None,
+                duration => Some(duration as f64 / AV_TIME_BASE as f64),
             }
-            ret
         }
     }
 
-    pub fn get_stream(&self, index: u32) -> Option<Stream> {
+    /// the best stream of media_type with a decoder for it, skipping streams no
+    /// decoder exists for. related is the index of a stream the result should
+    /// belong to, i.e. the audio of the program of a video stream. use
+    /// select_stream() to pick by language.
+    pub fn best_stream(
+        &self,
+        media_type: MediaType,
+        related: Option<usize>,
+    ) -> Result<(Stream<'_>, Decoder)> {
+        let related = related.map_or(-1, |index| index as c_int);
+        // streams without a decoder are skipped when decoder_ret is given
+        let mut codec = null_mut();
+        let index = unsafe {
+            check(av_find_best_stream(
+                self.fmt,
+                media_type.into(),
+                -1,
+                related,
+                &mut codec,
+                0,
+            ))?
+        };
+
+        let stream = self
+            .get_stream(index as usize)
+            .ok_or_else(|| Error::from_code(AVERROR_STREAM_NOT_FOUND))?;
+        let decoder = unsafe { Decoder::with_stream_and_codec(stream, codec)? };
+        Ok((stream, decoder))
+    }
+
+    /// the stream that best matches the preferences of selector, if any
+    pub fn select_stream(&self, selector: &StreamSelector) -> Option<Stream<'_>> {
+        selector.select(self)
+    }
+
+    pub fn get_stream(&self, index: usize) -> Option<Stream<'_>> {
         unsafe {
-            let fmt = *self.fmt;
-            if index < fmt.nb_streams {
-                let st = *(fmt.streams.offset(index as isize) as *mut *mut AVStream);
-                Some(Stream::wrap(st))
+            let fmt = &*self.fmt;
+            if index < fmt.nb_streams as usize {
+                Some(Stream::wrap(&**fmt.streams.add(index)))
             } else {
                 None
             }
         }
     }
 
-    pub fn get_stream_count(&self) -> u32 {
-        unsafe { (*self.fmt).nb_streams }
+    pub fn get_stream_count(&self) -> usize {
+        unsafe { (*self.fmt).nb_streams as usize }
+    }
+
+    pub fn streams(&self) -> impl Iterator<Item = Stream<'_>> {
+        (0..self.get_stream_count()).filter_map(move |i| self.get_stream(i))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeekMode {
+    /// the nearest keyframe at or before the timestamp
+    Keyframe,
+    /// the nearest frame at or before the timestamp, which may not be a keyframe
+    /// and can't be decoded correctly on its own
+    Any,
+    /// the timestamp is a byte position in the input
+    Byte,
+    /// the timestamp is a frame number, not supported by all demuxers
+    Frame,
+}
+
+pub struct Packets<'a> {
+    input: &'a Input,
+    stream_index: Option<usize>,
+    // all packets are read into it, the returned ones are moved out of it
+    packet: Packet,
+    done: bool,
+}
+
+impl<'a> Packets<'a> {
+    fn new(input: &'a Input, stream_index: Option<usize>) -> Self {
+        Packets {
+            input,
+            stream_index,
+            packet: Packet::new(),
+            done: false,
+        }
+    }
+}
+
+/// ends at end of file or after the first error other than Error::Again
+impl<'a> Iterator for Packets<'a> {
+    type Item = Result<(Stream<'a>, Packet)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        loop {
+            match self.input.read_packet(&mut self.packet) {
+                Ok(()) => {}
+                Err(Error::Eof) => {
+                    self.done = true;
+                    return None;
+                }
+                Err(Error::Again) => return Some(Err(Error::Again)),
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+
+            let index = self.packet.stream_index();
+            if self.stream_index.is_none() || self.stream_index == Some(index) {
+                if let Some(stream) = self.input.get_stream(index) {
+                    return Some(Ok((stream, self.packet.take())));
+                }
+            }
+            self.packet.unref();
+        }
     }
 }
@@ -0,0 +1,106 @@
+use super::avio::IoContext;
+use crate::ff_error::*;
+use crate::*;
+use log::warn;
+use std::ffi::CString;
+use std::io::{Cursor, Read, Seek};
+use std::ptr;
+
+/// opens an Input with a forced demuxer and/or demuxer options
+pub struct InputBuilder {
+    format: Option<CString>,
+    options: Dictionary,
+    find_stream_info: bool,
+}
+
+impl Default for InputBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InputBuilder {
+    pub fn new() -> Self {
+        InputBuilder {
+            format: None,
+            options: Dictionary::new(),
+            find_stream_info: true,
+        }
+    }
+
+    /// force a demuxer instead of probing, i.e. "rawvideo", "s16le", "image2"
+    pub fn format(mut self, name: &str) -> Result<Self> {
+        self.format = Some(CString::new(name)?);
+        Ok(self)
+    }
+
+    /// generic or demuxer-private option, i.e. ("probesize", "32"),
+    /// ("video_size", "1280x720"), ("pixel_format", "yuv420p")
+    pub fn option(mut self, key: &str, value: &str) -> Result<Self> {
+        self.options.insert(key, value)?;
+        Ok(self)
+    }
+
+    /// replaces the options set so far
+    pub fn options(mut self, options: Dictionary) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// whether to read some packets to fill in missing stream information,
+    /// true by default. skipping it opens faster if the container headers are
+    /// complete, or for raw inputs whose parameters are given as options
+    pub fn find_stream_info(mut self, find_stream_info: bool) -> Self {
+        self.find_stream_info = find_stream_info;
+        self
+    }
+
+    pub fn open_url(&self, url: &str) -> Result<Input> {
+        let c_url = CString::new(url)?;
+        self.open(&c_url, None)
+    }
+
+    pub fn open_reader<R: Read + Seek + Send + 'static>(&self, reader: R) -> Result<Input> {
+        let io = IoContext::with_reader(reader)?;
+        self.open(&CString::default(), Some(io))
+    }
+
+    pub fn open_unseekable_reader<R: Read + Send + 'static>(&self, reader: R) -> Result<Input> {
+        let io = IoContext::with_unseekable_reader(reader)?;
+        self.open(&CString::default(), Some(io))
+    }
+
+    pub fn open_bytes(&self, bytes: Vec<u8>) -> Result<Input> {
+        self.open_reader(Cursor::new(bytes))
+    }
+
+    fn open(&self, c_url: &CString, io: Option<IoContext>) -> Result<Input> {
+        unsafe {
+            let format = match &self.format {
+                Some(name) => {
+                    let format = av_find_input_format(name.as_ptr());
+                    if format.is_null() {
+                        warn!("demuxer not found: {:?}", name);
+                        return Err(Error::DemuxerNotFound);
+                    }
+                    format
+                }
+                None => ptr::null_mut(),
+            };
+
+            let mut options = self.options.clone();
+            let mut input = Input::open(
+                c_url,
+                io,
+                format,
+                options.as_mut_ptr_ref(),
+                self.find_stream_info,
+            )?;
+            for (key, _) in &options {
+                warn!("demuxer option not found: {}", key);
+            }
+            input.set_unused_options(options);
+            Ok(input)
+        }
+    }
+}
@@ -1,5 +1,22 @@
+pub mod attachment;
+mod avio;
+pub mod chapter;
+pub mod disposition;
 pub mod input;
+pub mod input_builder;
+pub mod output;
+pub mod program;
+pub mod remux;
 pub mod stream;
+pub mod stream_selector;
 
-pub use self::input::Input;
+pub use self::attachment::{AttachedPicture, Attachment};
+pub use self::chapter::Chapter;
+pub use self::disposition::Disposition;
+pub use self::input::{Input, Packets, SeekMode};
+pub use self::input_builder::InputBuilder;
+pub use self::output::Output;
+pub use self::program::Program;
+pub use self::remux::{remux, DroppedStream, RemuxOptions, RemuxReport};
 pub use self::stream::Stream;
+pub use self::stream_selector::StreamSelector;
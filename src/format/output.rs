@@ -0,0 +1,317 @@
+use crate::ff_error::*;
+use crate::*;
+use libc::{c_int, EINVAL, ENOMEM};
+use log::{error, warn};
+use std::ffi::{CStr, CString};
+use std::ops::Deref;
+use std::ptr::{self, null_mut};
+
+pub struct Output {
+    fmt: *mut AVFormatContext,
+    // time base of the packets passed to write_packet(), indexed by stream
+    packet_time_bases: Vec<Rational>,
+    header_written: bool,
+    trailer_written: bool,
+}
+
+impl Drop for Output {
+    fn drop(&mut self) {
+        if self.header_written && !self.trailer_written {
+            if let Err(e) = self.write_trailer() {
+                error!("av_write_trailer failed: {}", e);
+            }
+        }
+
+        unsafe {
+            if !self.fmt.is_null() {
+                if (*(*self.fmt).oformat).flags & AVFMT_NOFILE as c_int == 0 {
+                    avio_closep(&mut (*self.fmt).pb);
+                }
+                avformat_free_context(self.fmt);
+            }
+        }
+    }
+}
+
+impl Deref for Output {
+    type Target = AVFormatContext;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.fmt }
+    }
+}
+
+impl Output {
+    /// the muxer is guessed from the extension of url
+    pub fn with_url(url: &str) -> Result<Self> {
+        Self::with_format(url, None)
+    }
+
+    /// format_name forces a muxer, i.e. "mp4", "matroska" or "mpegts"
+    pub fn with_format(url: &str, format_name: Option<&str>) -> Result<Self> {
+        let c_url = CString::new(url)?;
+        let c_format_name = format_name.map(CString::new).transpose()?;
+
+        unsafe {
+            let mut fmt = null_mut();
+            check(avformat_alloc_output_context2(
+                &mut fmt,
+                null_mut(),
+                c_format_name.as_ref().map_or(ptr::null(), |n| n.as_ptr()),
+                c_url.as_ptr(),
+            ))?;
+            if fmt.is_null() {
+                return Err(Error::Io(ENOMEM));
+            }
+
+            if (*(*fmt).oformat).flags & AVFMT_NOFILE as c_int == 0 {
+                if let Err(e) = check(avio_open(
+                    &mut (*fmt).pb,
+                    c_url.as_ptr(),
+                    AVIO_FLAG_WRITE as c_int,
+                )) {
+                    avformat_free_context(fmt);
+                    return Err(e);
+                }
+            }
+
+            Ok(Output {
+                fmt,
+                packet_time_bases: Vec::new(),
+                header_written: false,
+                trailer_written: false,
+            })
+        }
+    }
+
+    /// add a stream described by params, packets later passed to write_packet()
+    /// for this stream are expected to be in packet_time_base, returns the index
+    /// of the new stream
+    pub fn add_stream(&mut self, params: &Parameters, packet_time_base: Rational) -> Result<usize> {
+        unsafe {
+            let st = avformat_new_stream(self.fmt, ptr::null());
+            if st.is_null() {
+                return Err(Error::Io(ENOMEM));
+            }
+
+            // the stream stays in the context even if the copy fails, the time
+            // bases of the following streams must keep their indices
+            (*st).time_base = packet_time_base.into();
+            self.packet_time_bases.push(packet_time_base);
+
+            check(avcodec_parameters_copy((*st).codecpar, params.as_ptr()))?;
+            // the tag of the source container may not be valid in this one
+            (*(*st).codecpar).codec_tag = 0;
+            Ok((*st).index as usize)
+        }
+    }
+
+    /// add a stream for the packets produced by encoder, the encoder must
+    /// have been opened with global_header(true) if needs_global_header()
+    pub fn add_stream_from_encoder(&mut self, encoder: &Encoder) -> Result<usize> {
+        unsafe {
+            let st = avformat_new_stream(self.fmt, ptr::null());
+            if st.is_null() {
+                return Err(Error::Io(ENOMEM));
+            }
+
+            // pushed before anything can fail, see add_stream()
+            (*st).time_base = encoder.time_base().into();
+            self.packet_time_bases.push(encoder.time_base());
+
+            check(avcodec_parameters_from_context(
+                (*st).codecpar,
+                encoder.as_ptr(),
+            ))?;
+            // read from the stream rather than codecpar by some muxers
+            (*st).sample_aspect_ratio = encoder.sample_aspect_ratio;
+            Ok((*st).index as usize)
+        }
+    }
+
+    /// whether encoders feeding this muxer should be built with global_header(true)
+    pub fn needs_global_header(&self) -> bool {
+        unsafe { (*(*self.fmt).oformat).flags & AVFMT_GLOBALHEADER as c_int != 0 }
+    }
+
+    /// the short name of the muxer, i.e. "mp4", "matroska", "mpegts"
+    pub fn format_name(&self) -> &str {
+        self.output_format().name()
+    }
+
+    /// whether the muxer can store streams of codec_id, None if it can't tell
+    pub fn supports_codec(&self, codec_id: CodecId) -> Option<bool> {
+        self.output_format().supports_codec(codec_id)
+    }
+
+    /// whether libavformat inserts the bitstream filters the muxer needs, i.e.
+    /// h264_mp4toannexb for MPEG-TS, on its own when packets are written. on
+    /// by default, turned off by callers that insert them themselves. must be
+    /// set before write_header().
+    pub fn set_auto_bitstream_filters(&mut self, enabled: bool) {
+        unsafe {
+            if enabled {
+                (*self.fmt).flags |= AVFMT_FLAG_AUTO_BSF as c_int;
+            } else {
+                (*self.fmt).flags &= !(AVFMT_FLAG_AUTO_BSF as c_int);
+            }
+        }
+    }
+
+    /// the container tags, must be set before write_header()
+    pub fn set_metadata(&mut self, metadata: &Dictionary) -> Result<()> {
+        unsafe {
+            check(av_dict_copy(
+                &mut (*self.fmt).metadata,
+                metadata.as_ptr(),
+                0,
+            ))
+            .map(|_| ())
+        }
+    }
+
+    /// the tags of the stream at index, must be set before write_header()
+    pub fn set_stream_metadata(&mut self, index: usize, metadata: &Dictionary) -> Result<()> {
+        let st = self.raw_stream(index)?;
+        unsafe { check(av_dict_copy(&mut (*st).metadata, metadata.as_ptr(), 0)).map(|_| ()) }
+    }
+
+    pub fn set_stream_disposition(&mut self, index: usize, disposition: Disposition) -> Result<()> {
+        let st = self.raw_stream(index)?;
+        unsafe { (*st).disposition = disposition.bits() };
+        Ok(())
+    }
+
+    pub fn get_stream_count(&self) -> usize {
+        unsafe { (*self.fmt).nb_streams as usize }
+    }
+
+    pub fn write_header(&mut self) -> Result<()> {
+        self.write_header_with_options(&Dictionary::new())
+    }
+
+    /// options are generic or muxer-private options, i.e. ("movflags", "+faststart")
+    pub fn write_header_with_options(&mut self, options: &Dictionary) -> Result<()> {
+        let mut options = options.clone();
+        unsafe {
+            check(avformat_write_header(self.fmt, options.as_mut_ptr_ref()))?;
+        }
+        for (key, _) in &options {
+            warn!("muxer option not found: {}", key);
+        }
+
+        self.header_written = true;
+        Ok(())
+    }
+
+    /// write the packet as is, the caller is responsible for interleaving,
+    /// timestamps are rescaled from the time base given in add_stream().
+    /// fails with Error::Io(EINVAL) before write_header()
+    pub fn write_packet(&mut self, packet: &mut Packet) -> Result<()> {
+        self.rescale_packet_ts(packet)?;
+        unsafe { check(av_write_frame(self.fmt, packet.as_mut_ptr())).map(|_| ()) }
+    }
+
+    /// buffer packets as necessary to make sure they are interleaved correctly
+    /// by dts, the packet is taken over by the muxer and left blank. fails
+    /// with Error::Io(EINVAL) before write_header()
+    pub fn write_interleaved_packet(&mut self, packet: &mut Packet) -> Result<()> {
+        self.rescale_packet_ts(packet)?;
+        unsafe { check(av_interleaved_write_frame(self.fmt, packet.as_mut_ptr())).map(|_| ()) }
+    }
+
+    /// flush the interleaving queue and finalize the file, called on drop if
+    /// not called explicitly
+    pub fn write_trailer(&mut self) -> Result<()> {
+        if !self.header_written || self.trailer_written {
+            return Ok(());
+        }
+
+        self.trailer_written = true;
+        unsafe { check(av_write_trailer(self.fmt)).map(|_| ()) }
+    }
+
+    pub(crate) fn output_format(&self) -> OutputFormat {
+        unsafe { OutputFormat((*self.fmt).oformat) }
+    }
+
+    fn raw_stream(&self, index: usize) -> Result<*mut AVStream> {
+        unsafe {
+            if index >= (*self.fmt).nb_streams as usize {
+                return Err(Error::from_code(AVERROR_STREAM_NOT_FOUND));
+            }
+            Ok(*(*self.fmt).streams.add(index))
+        }
+    }
+
+    fn rescale_packet_ts(&self, packet: &mut Packet) -> Result<()> {
+        // the muxer crashes on packets written before the header
+        if !self.header_written {
+            error!("write_header() must be called before writing packets");
+            return Err(Error::Io(EINVAL));
+        }
+        if self.trailer_written {
+            error!("can't write packets after the trailer");
+            return Err(Error::Io(EINVAL));
+        }
+
+        let index = packet.stream_index();
+        let src_tb = match self.packet_time_bases.get(index) {
+            Some(tb) => *tb,
+            None => return Err(Error::from_code(AVERROR_STREAM_NOT_FOUND)),
+        };
+
+        unsafe {
+            // the muxer may have changed the time base in avformat_write_header
+            let st = *(*self.fmt).streams.add(index);
+            packet.rescale_ts(src_tb, (*st).time_base.into());
+        }
+        Ok(())
+    }
+}
+
+/// a muxer, looked up without creating an Output, i.e. to check which streams
+/// it can store before the output file is created
+#[derive(Clone, Copy)]
+pub(crate) struct OutputFormat(*const AVOutputFormat);
+
+impl OutputFormat {
+    /// the muxer Output::with_format(url, format_name) would use
+    pub fn guess(url: &str, format_name: Option<&str>) -> Result<Self> {
+        let c_url = CString::new(url)?;
+        let c_format_name = format_name.map(CString::new).transpose()?;
+        let ofmt = unsafe {
+            av_guess_format(
+                c_format_name.as_ref().map_or(ptr::null(), |n| n.as_ptr()),
+                c_url.as_ptr(),
+                ptr::null(),
+            )
+        };
+        if ofmt.is_null() {
+            error!("failed to find muxer for {}", format_name.unwrap_or(url));
+            return Err(Error::from_code(AVERROR_MUXER_NOT_FOUND));
+        }
+        Ok(OutputFormat(ofmt))
+    }
+
+    pub fn name(&self) -> &'static str {
+        unsafe {
+            let name = (*self.0).name;
+            if name.is_null() {
+                ""
+            } else {
+                CStr::from_ptr(name).to_str().unwrap_or("")
+            }
+        }
+    }
+
+    pub fn supports_codec(&self, codec_id: CodecId) -> Option<bool> {
+        unsafe {
+            match avformat_query_codec(self.0, codec_id.into(), FF_COMPLIANCE_NORMAL as c_int) {
+                1 => Some(true),
+                0 => Some(false),
+                _ => None,
+            }
+        }
+    }
+}
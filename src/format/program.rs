@@ -0,0 +1,43 @@
+use crate::*;
+use std::slice;
+
+/// a program of a multi-program input, i.e. a service of an MPEG-TS
+#[derive(Debug, Clone)]
+pub struct Program {
+    pub id: i32,
+    pub program_num: i32,
+    /// -1 if unknown
+    pub pmt_pid: i32,
+    /// -1 if unknown
+    pub pcr_pid: i32,
+    /// indexes of the streams of the Input that belong to this program
+    pub stream_indexes: Vec<usize>,
+    /// i.e. "service_name", "service_provider"
+    pub metadata: Dictionary,
+}
+
+impl Program {
+    pub(crate) unsafe fn from_raw(program: &AVProgram) -> Self {
+        let stream_indexes = if program.stream_index.is_null() {
+            Vec::new()
+        } else {
+            slice::from_raw_parts(program.stream_index, program.nb_stream_indexes as usize)
+                .iter()
+                .map(|&i| i as usize)
+                .collect()
+        };
+
+        Program {
+            id: program.id,
+            program_num: program.program_num,
+            pmt_pid: program.pmt_pid,
+            pcr_pid: program.pcr_pid,
+            stream_indexes,
+            metadata: Dictionary::copy_from(program.metadata),
+        }
+    }
+
+    pub fn service_name(&self) -> Option<&str> {
+        self.metadata.get("service_name")
+    }
+}
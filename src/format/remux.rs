@@ -0,0 +1,384 @@
+use super::output::OutputFormat;
+use crate::ff_error::*;
+use crate::*;
+use libc::EINVAL;
+use log::{error, info, warn};
+use std::fs;
+use std::path::Path;
+
+/// what remux() copies and how
+#[derive(Debug, Clone, Default)]
+pub struct RemuxOptions {
+    format: Option<String>,
+    stream_indexes: Vec<usize>,
+    media_types: Vec<MediaType>,
+    start: Option<f64>,
+    end: Option<f64>,
+    muxer_options: Dictionary,
+}
+
+impl RemuxOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// force a muxer, i.e. "mp4", instead of guessing it from the output path
+    pub fn format(mut self, format: &str) -> Self {
+        self.format = Some(format.to_owned());
+        self
+    }
+
+    /// copy only the streams at these indexes of the input, takes precedence
+    /// over media_types()
+    pub fn streams(mut self, indexes: &[usize]) -> Self {
+        self.stream_indexes = indexes.to_vec();
+        self
+    }
+
+    /// copy only the streams of these types, all streams by default
+    pub fn media_types(mut self, media_types: &[MediaType]) -> Self {
+        self.media_types = media_types.to_vec();
+        self
+    }
+
+    /// in seconds from the start of the input, the output starts at the
+    /// keyframe before start
+    pub fn start(mut self, start: f64) -> Self {
+        self.start = Some(start);
+        self
+    }
+
+    /// in seconds from the start of the input
+    pub fn end(mut self, end: f64) -> Self {
+        self.end = Some(end);
+        self
+    }
+
+    /// generic or muxer-private option, i.e. ("movflags", "+faststart")
+    pub fn muxer_option(mut self, key: &str, value: &str) -> Result<Self> {
+        self.muxer_options.insert(key, value)?;
+        Ok(self)
+    }
+
+    fn is_selected(&self, stream: &Stream) -> bool {
+        if !self.stream_indexes.is_empty() {
+            self.stream_indexes.contains(&stream.index())
+        } else if !self.media_types.is_empty() {
+            self.media_types.contains(&stream.media_type())
+        } else {
+            true
+        }
+    }
+}
+
+/// a selected stream that was not copied
+#[derive(Debug, Clone)]
+pub struct DroppedStream {
+    pub index: usize,
+    pub codec_id: CodecId,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RemuxReport {
+    /// (input index, output index) of the copied streams
+    pub streams: Vec<(usize, usize)>,
+    pub dropped: Vec<DroppedStream>,
+    /// (input index, filter) of the bitstream filters that were inserted
+    pub bitstream_filters: Vec<(usize, String)>,
+    pub packets_written: u64,
+}
+
+struct RemuxStream {
+    out_index: usize,
+    time_base: Rational,
+    bsf: Option<BitstreamFilter>,
+    is_video: bool,
+    // video streams start at a keyframe
+    started: bool,
+    ended: bool,
+}
+
+/// copy the streams of input into a new file at output_path without
+/// re-encoding, i.e. MKV to MP4 or TS to MP4. streams the muxer can't store
+/// are dropped and listed in the report. the bitstream filters the muxer needs
+/// (i.e. h264_mp4toannexb for MPEG-TS) are inserted here and listed in the
+/// report, the automatic insertion of libavformat is turned off. the output
+/// file is only created once the streams to copy are known, and removed if
+/// remuxing fails.
+///
+/// the timestamps of the output start at 0, or at start() for a trimmed
+/// output, the packets between the keyframe before it and start() then have
+/// negative timestamps.
+pub fn remux(input: &mut Input, output_path: &str, options: &RemuxOptions) -> Result<RemuxReport> {
+    let output_format = OutputFormat::guess(output_path, options.format.as_deref())?;
+    let format_name = output_format.name();
+    let mut report = RemuxReport::default();
+
+    // (input index, bitstream filter) of the streams to copy
+    let mut selected = Vec::new();
+    for stream in input.streams() {
+        if !options.is_selected(&stream) {
+            continue;
+        }
+
+        let params = stream.codec_parameters();
+        let codec_id = params.codec_id();
+        if let Some(reason) = unsupported_reason(output_format, codec_id) {
+            warn!("dropping stream {}: {}", stream.index(), reason);
+            report.dropped.push(DroppedStream {
+                index: stream.index(),
+                codec_id,
+                reason,
+            });
+            continue;
+        }
+        selected.push((stream.index(), required_bsf(&params, format_name)));
+    }
+
+    if selected.is_empty() {
+        error!("no streams to remux into {}", output_path);
+        return Err(Error::Io(EINVAL));
+    }
+
+    let mut output = Output::with_format(output_path, options.format.as_deref())?;
+    output.set_auto_bitstream_filters(false);
+    if let Err(e) = copy_streams(input, &mut output, selected, options, &mut report) {
+        discard_output(output, output_path);
+        return Err(e);
+    }
+
+    info!(
+        "remuxed {} streams into {}, {} packets, {} streams dropped",
+        report.streams.len(),
+        output_path,
+        report.packets_written,
+        report.dropped.len()
+    );
+    Ok(report)
+}
+
+fn copy_streams(
+    input: &mut Input,
+    output: &mut Output,
+    selected: Vec<(usize, Option<&'static str>)>,
+    options: &RemuxOptions,
+    report: &mut RemuxReport,
+) -> Result<()> {
+    let mut streams: Vec<Option<RemuxStream>> =
+        (0..input.get_stream_count()).map(|_| None).collect();
+
+    for (index, bsf_name) in selected {
+        let stream = input
+            .get_stream(index)
+            .ok_or_else(|| Error::from_code(AVERROR_STREAM_NOT_FOUND))?;
+        let bsf = match bsf_name {
+            Some(name) => {
+                report.bitstream_filters.push((index, name.to_owned()));
+                Some(BitstreamFilter::with_stream(name, stream)?)
+            }
+            None => None,
+        };
+        let out_index = match &bsf {
+            Some(bsf) => output.add_stream(&bsf.output_parameters(), bsf.output_time_base())?,
+            None => output.add_stream(&stream.codec_parameters(), stream.time_base())?,
+        };
+        output.set_stream_metadata(out_index, &stream.metadata())?;
+        output.set_stream_disposition(out_index, stream.disposition())?;
+
+        report.streams.push((index, out_index));
+        streams[index] = Some(RemuxStream {
+            out_index,
+            time_base: stream.time_base(),
+            bsf,
+            is_video: stream.is_video_stream() && !stream.is_attached_picture(),
+            started: false,
+            ended: false,
+        });
+    }
+
+    output.set_metadata(&input.metadata())?;
+    output.write_header_with_options(&options.muxer_options)?;
+
+    let input_start = match input.start_time {
+        AV_NOPTS_VALUE => 0,
+        start_time => start_time,
+    };
+    let input_start_secs = input_start as f64 / AV_TIME_BASE as f64;
+    // packets before start are dropped if the input can't seek to it
+    let mut drop_before = None;
+    if let Some(start) = options.start {
+        let ts = ((input_start_secs + start) * AV_TIME_BASE as f64) as i64;
+        if let Err(e) = input.seek(None, ts, SeekMode::Keyframe) {
+            warn!(
+                "failed to seek to {}s, skipping packets instead: {}",
+                start, e
+            );
+            drop_before = Some(start);
+        }
+    }
+
+    // in AV_TIME_BASE units, subtracted from the timestamps of all streams,
+    // rescaled to the time base of each
+    let av_time_base = Rational::new(1, AV_TIME_BASE as i32);
+    let offset = input_start
+        + options
+            .start
+            .map_or(0, |start| (start * AV_TIME_BASE as f64) as i64);
+
+    for item in input.packets() {
+        let (stream, mut packet) = item?;
+        let st = match streams.get_mut(stream.index()) {
+            Some(Some(st)) if !st.ended => st,
+            _ => continue,
+        };
+
+        let ts = packet.pts().or_else(|| packet.dts());
+        let time = ts.map(|ts| ts as f64 * st.time_base.as_f64() - input_start_secs);
+        if let (Some(end), Some(time)) = (options.end, time) {
+            if time >= end {
+                st.ended = true;
+                if streams.iter().flatten().all(|st| st.ended) {
+                    break;
+                }
+                continue;
+            }
+        }
+        if let (Some(start), Some(time)) = (drop_before, time) {
+            if time < start {
+                continue;
+            }
+        }
+        if st.is_video && !st.started && !packet.is_key() {
+            continue;
+        }
+        st.started = true;
+
+        let shift = av_time_base.rescale(offset, st.time_base);
+        packet.set_pts(packet.pts().map(|pts| pts - shift));
+        packet.set_dts(packet.dts().map(|dts| dts - shift));
+        packet.set_position(None);
+
+        let packets = match st.bsf.as_mut() {
+            Some(bsf) => bsf.filter(&packet)?,
+            None => vec![packet],
+        };
+        for mut packet in packets {
+            packet.set_stream_index(st.out_index);
+            output.write_interleaved_packet(&mut packet)?;
+            report.packets_written += 1;
+        }
+    }
+
+    for st in streams.iter_mut().flatten() {
+        if let Some(bsf) = st.bsf.as_mut() {
+            for mut packet in bsf.drain()? {
+                packet.set_stream_index(st.out_index);
+                output.write_interleaved_packet(&mut packet)?;
+                report.packets_written += 1;
+            }
+        }
+    }
+    output.write_trailer()
+}
+
+// why streams of codec_id can't be copied into output_format, if they can't
+pub(crate) fn unsupported_reason(output_format: OutputFormat, codec_id: CodecId) -> Option<String> {
+    match codec_id {
+        CodecId::None => Some("unknown codec".to_owned()),
+        _ if output_format.supports_codec(codec_id) == Some(false) => Some(format!(
+            "{} can't store {}",
+            output_format.name(),
+            codec_id.name()
+        )),
+        _ => None,
+    }
+}
+
+// close and delete the file of an output that failed half-way, it would be
+// left truncated or without a trailer otherwise
+pub(crate) fn discard_output(output: Output, path: &str) {
+    drop(output);
+    let path = Path::new(path);
+    if path.is_file() {
+        if let Err(e) = fs::remove_file(path) {
+            warn!("failed to remove {}: {}", path.display(), e);
+        }
+    }
+}
+
+// the bitstream filter needed to store a stream of params in the muxer
+pub(crate) fn required_bsf(params: &Parameters, format_name: &str) -> Option<&'static str> {
+    bsf_for(params.codec_id(), params.extradata(), format_name)
+}
+
+fn bsf_for(codec_id: CodecId, extradata: &[u8], format_name: &str) -> Option<&'static str> {
+    let annexb_muxer = matches!(format_name, "mpegts" | "h264" | "hevc");
+    let mp4_like_muxer = matches!(
+        format_name,
+        "mp4" | "mov" | "ipod" | "3gp" | "3g2" | "psp" | "ismv" | "f4v" | "flv" | "matroska"
+    );
+    // avcC and hvcC start with configurationVersion 1, Annex-B with a start code
+    let length_prefixed = extradata.first() == Some(&1);
+
+    match codec_id {
+        CodecId::H264 if annexb_muxer && length_prefixed => Some("h264_mp4toannexb"),
+        CodecId::Hevc if annexb_muxer && length_prefixed => Some("hevc_mp4toannexb"),
+        // ADTS streams (i.e. from MPEG-TS) carry no AudioSpecificConfig
+        CodecId::Aac if mp4_like_muxer && extradata.is_empty() => Some("aac_adtstoasc"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AVCC: &[u8] = &[1, 0x64, 0, 0x1f, 0xff];
+    const ANNEXB: &[u8] = &[0, 0, 0, 1, 0x67];
+    // AudioSpecificConfig of AAC LC 44.1kHz stereo
+    const ASC: &[u8] = &[0x12, 0x10];
+
+    #[test]
+    fn annexb_for_mpegts() {
+        assert_eq!(
+            bsf_for(CodecId::H264, AVCC, "mpegts"),
+            Some("h264_mp4toannexb")
+        );
+        assert_eq!(
+            bsf_for(CodecId::Hevc, AVCC, "mpegts"),
+            Some("hevc_mp4toannexb")
+        );
+        assert_eq!(
+            bsf_for(CodecId::H264, AVCC, "h264"),
+            Some("h264_mp4toannexb")
+        );
+        // already Annex-B, i.e. from another TS
+        assert_eq!(bsf_for(CodecId::H264, ANNEXB, "mpegts"), None);
+        assert_eq!(bsf_for(CodecId::H264, &[], "mpegts"), None);
+    }
+
+    #[test]
+    fn no_annexb_for_mp4() {
+        assert_eq!(bsf_for(CodecId::H264, AVCC, "mp4"), None);
+        assert_eq!(bsf_for(CodecId::H264, ANNEXB, "mp4"), None);
+        assert_eq!(bsf_for(CodecId::Hevc, AVCC, "matroska"), None);
+    }
+
+    #[test]
+    fn adts_to_asc_for_mp4_like_muxers() {
+        for format in ["mp4", "mov", "flv", "matroska"].iter() {
+            assert_eq!(bsf_for(CodecId::Aac, &[], format), Some("aac_adtstoasc"));
+            assert_eq!(bsf_for(CodecId::Aac, ASC, format), None);
+        }
+        assert_eq!(bsf_for(CodecId::Aac, &[], "mpegts"), None);
+        assert_eq!(bsf_for(CodecId::Aac, &[], "adts"), None);
+    }
+
+    #[test]
+    fn other_codecs() {
+        assert_eq!(bsf_for(CodecId::Mp3, &[], "mp4"), None);
+        assert_eq!(bsf_for(CodecId::Opus, &[], "mpegts"), None);
+        assert_eq!(bsf_for(CodecId::Vp9, &[1], "mpegts"), None);
+    }
+}
@@ -1,40 +1,171 @@
 use crate::*;
-use std::ops::Deref;
+use std::ffi::{CStr, CString};
+use std::ptr;
+use std::slice;
 
-pub struct Stream {
-    stream: *mut AVStream,
+/// a stream of an Input, it can't outlive the Input it was obtained from
+#[derive(Clone, Copy)]
+pub struct Stream<'a> {
+    stream: &'a AVStream,
 }
 
-impl Deref for Stream {
-    type Target = AVStream;
-
-    fn deref(&self) -> &Self::Target {
-        unsafe { &*self.stream }
-    }
-}
-
-impl Stream {
-    pub fn wrap(stream: *mut AVStream) -> Self {
+impl<'a> Stream<'a> {
+    pub(crate) fn wrap(stream: &'a AVStream) -> Self {
         Stream { stream }
     }
 
+    /// true for cover art too, see is_attached_picture()
     pub fn is_video_stream(&self) -> bool {
-        unsafe { (*self.codecpar).codec_type == AVMediaType_AVMEDIA_TYPE_VIDEO }
+        self.media_type() == MediaType::Video
     }
 
     pub fn is_audio_stream(&self) -> bool {
-        unsafe { (*self.codecpar).codec_type == AVMediaType_AVMEDIA_TYPE_AUDIO }
+        self.media_type() == MediaType::Audio
     }
 
     pub fn is_subtitle_stream(&self) -> bool {
-        unsafe { (*self.codecpar).codec_type == AVMediaType_AVMEDIA_TYPE_SUBTITLE }
+        self.media_type() == MediaType::Subtitle
+    }
+
+    /// a video stream that holds a single embedded picture, i.e. album art
+    pub fn is_attached_picture(&self) -> bool {
+        self.disposition().contains(Disposition::ATTACHED_PIC)
+    }
+
+    /// an attached file, i.e. a font in Matroska
+    pub fn is_attachment_stream(&self) -> bool {
+        self.media_type() == MediaType::Attachment
+    }
+
+    pub fn media_type(&self) -> MediaType {
+        unsafe { (*self.stream.codecpar).codec_type.into() }
+    }
+
+    pub fn codec_id(&self) -> CodecId {
+        unsafe { (*self.stream.codecpar).codec_id.into() }
+    }
+
+    /// a copy of the codec parameters of the stream
+    pub fn codec_parameters(&self) -> Parameters {
+        unsafe { Parameters::copy_from(self.stream.codecpar) }
     }
 
-    pub fn codecpar(&self) -> *const AVCodecParameters {
-        self.deref().codecpar
+    /// index of the stream in the Input, as in Packet::stream_index()
+    pub fn index(&self) -> usize {
+        self.stream.index as usize
     }
 
+    /// format specific stream id
     pub fn id(&self) -> i32 {
-        self.deref().id
+        self.stream.id
+    }
+
+    /// the unit of the timestamps of the packets of this stream
+    pub fn time_base(&self) -> Rational {
+        self.stream.time_base.into()
+    }
+
+    /// average frame rate, 0/0 if unknown
+    pub fn avg_frame_rate(&self) -> Rational {
+        self.stream.avg_frame_rate.into()
+    }
+
+    /// the lowest frame rate with which all timestamps can be represented
+    /// accurately, 0/0 if unknown
+    pub fn r_frame_rate(&self) -> Rational {
+        self.stream.r_frame_rate.into()
+    }
+
+    /// in time_base units
+    pub fn duration(&self) -> Option<i64> {
+        match self.stream.duration {
+            AV_NOPTS_VALUE => None,
+            duration => Some(duration),
+        }
+    }
+
+    /// pts of the first frame in time_base units
+    pub fn start_time(&self) -> Option<i64> {
+        match self.stream.start_time {
+            AV_NOPTS_VALUE => None,
+            start_time => Some(start_time),
+        }
+    }
+
+    /// number of frames if known
+    pub fn nb_frames(&self) -> Option<u64> {
+        match self.stream.nb_frames {
+            n if n > 0 => Some(n as u64),
+            _ => None,
+        }
+    }
+
+    pub fn disposition(&self) -> Disposition {
+        Disposition::from_bits(self.stream.disposition)
+    }
+
+    /// a copy of the stream tags, i.e. "language", "title", "handler_name"
+    pub fn metadata(&self) -> Dictionary {
+        unsafe { Dictionary::copy_from(self.stream.metadata) }
+    }
+
+    /// ISO 639-2 language code, i.e. "eng"
+    pub fn language(&self) -> Option<String> {
+        self.tag("language")
+    }
+
+    pub fn title(&self) -> Option<String> {
+        self.tag("title")
+    }
+
+    /// the mp4/mov handler name, i.e. "VideoHandler", "SoundHandler"
+    pub fn handler_name(&self) -> Option<String> {
+        self.tag("handler_name")
+    }
+
+    /// the picture of a stream with the ATTACHED_PIC disposition
+    pub fn attached_picture(&self) -> Option<AttachedPicture> {
+        let pkt = &self.stream.attached_pic;
+        if !self.is_attached_picture() || pkt.data.is_null() || pkt.size <= 0 {
+            return None;
+        }
+
+        let data = unsafe { slice::from_raw_parts(pkt.data, pkt.size as usize) };
+        Some(AttachedPicture {
+            stream_index: self.index(),
+            codec_id: self.codec_id(),
+            data: data.to_vec(),
+            comment: self.tag("comment"),
+        })
+    }
+
+    /// the file of an attachment stream, stored in the codec extradata
+    pub fn attachment(&self) -> Option<Attachment> {
+        if !self.is_attachment_stream() {
+            return None;
+        }
+
+        Some(Attachment {
+            stream_index: self.index(),
+            filename: self.tag("filename"),
+            mime_type: self.tag("mimetype"),
+            data: self.codec_parameters().extradata().to_vec(),
+        })
+    }
+
+    fn tag(&self, key: &str) -> Option<String> {
+        let key = CString::new(key).ok()?;
+        unsafe {
+            let entry = av_dict_get(self.stream.metadata, key.as_ptr(), ptr::null(), 0);
+            if entry.is_null() {
+                None
+            } else {
+                Some(
+                    CStr::from_ptr((*entry).value)
+                        .to_string_lossy()
+                        .into_owned(),
+                )
+            }
+        }
     }
 }
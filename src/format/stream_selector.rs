@@ -0,0 +1,215 @@
+use crate::*;
+use std::cmp::Reverse;
+
+/// picks a stream by preferences instead of by index, i.e. the english
+/// non-commentary audio stream or the video stream with the highest resolution
+#[derive(Debug, Clone)]
+pub struct StreamSelector {
+    media_type: MediaType,
+    languages: Vec<String>,
+    prefer_non_commentary: bool,
+    prefer_highest_resolution: bool,
+}
+
+impl StreamSelector {
+    pub fn new(media_type: MediaType) -> Self {
+        StreamSelector {
+            media_type,
+            languages: Vec::new(),
+            prefer_non_commentary: false,
+            prefer_highest_resolution: false,
+        }
+    }
+
+    /// the "language" tag to prefer, usually ISO 639-2 (i.e. "eng", "fre"),
+    /// compared case-insensitively. languages added first are preferred.
+    pub fn language(mut self, language: &str) -> Self {
+        self.languages.push(language.to_lowercase());
+        self
+    }
+
+    /// replaces the languages added so far, in order of preference
+    pub fn languages<I, S>(mut self, languages: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.languages = languages
+            .into_iter()
+            .map(|l| l.as_ref().to_lowercase())
+            .collect();
+        self
+    }
+
+    /// avoid streams with the COMMENT disposition or "commentary" in the title
+    pub fn prefer_non_commentary(mut self, prefer: bool) -> Self {
+        self.prefer_non_commentary = prefer;
+        self
+    }
+
+    /// video only, prefer the stream with the most pixels
+    pub fn prefer_highest_resolution(mut self, prefer: bool) -> Self {
+        self.prefer_highest_resolution = prefer;
+        self
+    }
+
+    /// the preferences are applied in the order: language, non-commentary,
+    /// resolution, then the DEFAULT disposition, ties go to the first stream.
+    /// attached pictures are never selected.
+    pub fn select<'a>(&self, input: &'a Input) -> Option<Stream<'a>> {
+        input
+            .streams()
+            .filter(|st| st.media_type() == self.media_type && !st.is_attached_picture())
+            .max_by_key(|st| self.rank(&Candidate::from_stream(st)))
+    }
+
+    // higher is better
+    fn rank(&self, candidate: &Candidate) -> (usize, bool, i64, bool, Reverse<usize>) {
+        (
+            self.language_rank(candidate),
+            self.prefer_non_commentary && !candidate.commentary,
+            if self.prefer_highest_resolution {
+                candidate.pixels
+            } else {
+                0
+            },
+            candidate.default,
+            Reverse(candidate.index),
+        )
+    }
+
+    // 0 for streams in none of the languages
+    fn language_rank(&self, candidate: &Candidate) -> usize {
+        let language = match &candidate.language {
+            Some(language) => language.to_lowercase(),
+            None => return 0,
+        };
+        self.languages
+            .iter()
+            .position(|l| *l == language)
+            .map_or(0, |pos| self.languages.len() - pos)
+    }
+}
+
+// the properties of a stream the preferences are applied to
+struct Candidate {
+    index: usize,
+    language: Option<String>,
+    commentary: bool,
+    // 0 for non-video streams
+    pixels: i64,
+    default: bool,
+}
+
+impl Candidate {
+    fn from_stream(stream: &Stream) -> Self {
+        let pixels = if stream.is_video_stream() {
+            let params = stream.codec_parameters();
+            params.width() as i64 * params.height() as i64
+        } else {
+            0
+        };
+        let disposition = stream.disposition();
+        Candidate {
+            index: stream.index(),
+            language: stream.language(),
+            commentary: disposition.contains(Disposition::COMMENT)
+                || matches!(stream.title(), Some(t) if t.to_lowercase().contains("commentary")),
+            pixels,
+            default: disposition.contains(Disposition::DEFAULT),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(index: usize, language: Option<&str>) -> Candidate {
+        Candidate {
+            index,
+            language: language.map(str::to_owned),
+            commentary: false,
+            pixels: 0,
+            default: false,
+        }
+    }
+
+    // the index of the candidate select() would pick
+    fn best(selector: &StreamSelector, candidates: &[Candidate]) -> usize {
+        candidates
+            .iter()
+            .max_by_key(|c| selector.rank(c))
+            .map(|c| c.index)
+            .unwrap()
+    }
+
+    #[test]
+    fn ties_go_to_the_first_stream() {
+        let selector = StreamSelector::new(MediaType::Audio);
+        let candidates = [candidate(1, None), candidate(2, None), candidate(3, None)];
+        assert_eq!(best(&selector, &candidates), 1);
+    }
+
+    #[test]
+    fn default_disposition_breaks_ties() {
+        let selector = StreamSelector::new(MediaType::Audio);
+        let mut second = candidate(2, None);
+        second.default = true;
+        assert_eq!(best(&selector, &[candidate(1, None), second]), 2);
+    }
+
+    #[test]
+    fn languages_in_order_of_preference() {
+        let selector = StreamSelector::new(MediaType::Audio)
+            .language("fre")
+            .language("ENG");
+        let candidates = [
+            candidate(0, Some("ger")),
+            candidate(1, Some("eng")),
+            candidate(2, Some("fre")),
+            candidate(3, None),
+        ];
+        assert_eq!(best(&selector, &candidates), 2);
+        assert_eq!(best(&selector, &candidates[..2]), 1);
+
+        let selector = StreamSelector::new(MediaType::Audio).languages(vec!["ita"]);
+        assert_eq!(best(&selector, &candidates), 0);
+    }
+
+    #[test]
+    fn language_takes_precedence_over_default() {
+        let selector = StreamSelector::new(MediaType::Audio).language("eng");
+        let mut first = candidate(0, Some("ger"));
+        first.default = true;
+        assert_eq!(best(&selector, &[first, candidate(1, Some("eng"))]), 1);
+    }
+
+    #[test]
+    fn non_commentary() {
+        let mut commentary = candidate(0, Some("eng"));
+        commentary.commentary = true;
+        commentary.default = true;
+        let candidates = [commentary, candidate(1, Some("eng"))];
+
+        let selector = StreamSelector::new(MediaType::Audio);
+        assert_eq!(best(&selector, &candidates), 0);
+        let selector = selector.prefer_non_commentary(true);
+        assert_eq!(best(&selector, &candidates), 1);
+    }
+
+    #[test]
+    fn highest_resolution() {
+        let mut small = candidate(0, None);
+        small.pixels = 1280 * 720;
+        small.default = true;
+        let mut large = candidate(1, None);
+        large.pixels = 1920 * 1080;
+        let candidates = [small, large];
+
+        let selector = StreamSelector::new(MediaType::Video);
+        assert_eq!(best(&selector, &candidates), 0);
+        let selector = selector.prefer_highest_resolution(true);
+        assert_eq!(best(&selector, &candidates), 1);
+    }
+}
@@ -1,29 +1,120 @@
+use crate::ff_error::*;
 use crate::*;
+use libc::ENOMEM;
 use std::ops::{Deref, DerefMut};
 
 pub struct Frame {
-    frame: AVFrame,
+    frame: *mut AVFrame,
+}
+
+impl Drop for Frame {
+    fn drop(&mut self) {
+        unsafe {
+            av_frame_free(&mut self.frame);
+        }
+    }
 }
 
 impl Deref for Frame {
     type Target = AVFrame;
 
     fn deref(&self) -> &Self::Target {
-        &self.frame
+        unsafe { &*self.frame }
     }
 }
 
 impl DerefMut for Frame {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.frame
+        unsafe { &mut *self.frame }
+    }
+}
+
+/// a shallow copy, see try_clone(), panics on allocation failure
+impl Clone for Frame {
+    fn clone(&self) -> Self {
+        match self.try_clone() {
+            Ok(frame) => frame,
+            Err(e) => panic!("av_frame_ref failed: {}", e),
+        }
+    }
+}
+
+impl Default for Frame {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl Frame {
     pub fn new() -> Self {
         unsafe {
-            let frame = std::mem::zeroed();
+            let frame = av_frame_alloc();
+            if frame.is_null() {
+                panic!("av_frame_alloc failed");
+            }
             Frame { frame }
         }
     }
+
+    /// a shallow copy, the data buffers are shared through reference counting.
+    /// a frame without buffers, i.e. a new one, is cloned as an empty frame.
+    pub fn try_clone(&self) -> Result<Self> {
+        unsafe {
+            let frame = av_frame_alloc();
+            if frame.is_null() {
+                return Err(Error::Io(ENOMEM));
+            }
+            let frame = Frame { frame };
+            // av_frame_ref would try to allocate buffers for it and fail
+            if (*self.frame).buf[0].is_null() && (*self.frame).data[0].is_null() {
+                return Ok(frame);
+            }
+            check(av_frame_ref(frame.frame, self.frame))?;
+            Ok(frame)
+        }
+    }
+
+    /// release the data buffers and reset all fields to their defaults
+    pub fn unref(&mut self) {
+        unsafe { av_frame_unref(self.frame) }
+    }
+
+    pub fn is_writable(&self) -> bool {
+        unsafe { av_frame_is_writable(self.frame) != 0 }
+    }
+
+    /// copy the data if the buffers are shared with other frames, so they can
+    /// be modified in place
+    pub fn make_writable(&mut self) -> Result<()> {
+        unsafe { check(av_frame_make_writable(self.frame)).map(|_| ()) }
+    }
+
+    /// a copy with its own data buffers
+    pub fn deep_clone(&self) -> Result<Self> {
+        let frame = Frame::new();
+        unsafe {
+            let dst = &mut *frame.frame;
+            let src = &*self.frame;
+            dst.format = src.format;
+            dst.width = src.width;
+            dst.height = src.height;
+            dst.nb_samples = src.nb_samples;
+            dst.channel_layout = src.channel_layout;
+            dst.channels = src.channels;
+            dst.sample_rate = src.sample_rate;
+
+            check(av_frame_get_buffer(dst, 0))?;
+            check(av_frame_copy(dst, src))?;
+            check(av_frame_copy_props(dst, src))?;
+        }
+        Ok(frame)
+    }
+
+    pub fn as_ptr(&self) -> *const AVFrame {
+        self.frame
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut AVFrame {
+        self.frame
+    }
 }
@@ -6,18 +6,35 @@
 mod ff_error;
 
 mod codec;
+mod ff_log;
+pub mod filter;
 mod format;
 mod frame;
 mod packet;
+mod software;
+mod transcode;
+mod util;
 
 #[macro_use]
 mod macros;
 
-pub use codec::Decoder;
-pub use format::{Input, Stream};
+pub use codec::{
+    BitstreamFilter, CodecId, Decoder, Encoder, EncoderBuilder, Frames, Parameters, Parser,
+};
+pub use ff_error::{Error, Result};
+pub use format::{
+    remux, AttachedPicture, Attachment, Chapter, Disposition, DroppedStream, Input, InputBuilder,
+    Output, Packets, Program, RemuxOptions, RemuxReport, SeekMode, Stream, StreamSelector,
+};
 pub use frame::Frame;
 pub use packet::Packet;
+pub use software::{Resampler, ScaleAlgorithm, Scaler};
 use std::sync::Once;
+pub use transcode::{EncodeSpec, Progress, StreamSpec, Transcoder};
+pub use util::{
+    Dictionary, DictionaryFlags, DictionaryIter, MediaType, PictureType, PixelFormat, Rational,
+    SampleFormat,
+};
 
 extern crate pretty_env_logger;
 
@@ -25,8 +42,24 @@ static INIT_LOGGER_ONCE: Once = Once::new();
 
 include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
 
+/// ((int64_t)UINT64_C(0x8000000000000000)) in libavutil/avutil.h, which bindgen
+/// can't translate
+pub const AV_NOPTS_VALUE: i64 = i64::MIN;
+
+/// None for AV_NOPTS_VALUE
+pub(crate) fn ts_to_option(ts: i64) -> Option<i64> {
+    if ts == AV_NOPTS_VALUE {
+        None
+    } else {
+        Some(ts)
+    }
+}
+
 pub fn init_logger(log_level: &str) {
-    INIT_LOGGER_ONCE.call_once(|| LogHelper::init_logger(log_level.as_ref()));
+    INIT_LOGGER_ONCE.call_once(|| {
+        LogHelper::init_logger(log_level.as_ref());
+        ff_log::redirect_av_log(log::max_level());
+    });
 }
 
 #[cfg(not(target_os = "android"))]
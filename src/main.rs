@@ -1,5 +1,4 @@
 use log::info;
-use rs_ffmpeg::Decoder;
 use rs_ffmpeg::*;
 
 fn main() {
@@ -8,24 +7,18 @@ fn main() {
     let url =
         "https://test-videos.co.uk/vids/bigbuckbunny/mp4/h264/360/Big_Buck_Bunny_360_10s_10MB.mp4";
 
-    let input = Input::with_url(url).unwrap();
-    let st = input.get_stream(0).unwrap();
-    let d = Decoder::with_stream(st).unwrap();
+    let mut input = Input::with_url(url).unwrap();
+    let (st, mut d) = input.best_stream(MediaType::Video, None).unwrap();
+    let index = st.index();
 
-    let mut packet = Packet::new();
-    input.read_packet(&mut *packet);
-    d.enqueue_packet(&packet);
-    input.read_packet(&mut *packet);
-    d.enqueue_packet(&packet);
-    input.read_packet(&mut *packet);
-    d.enqueue_packet(&packet);
-    input.read_packet(&mut *packet);
-    d.enqueue_packet(&packet);
+    let mut frame_count = 0;
+    for (_, packet) in input.stream_packets(index).take(4).map(Result::unwrap) {
+        for frame in d.decode(&packet).map(Result::unwrap) {
+            info!(">>>>>>>>>> width:{}, pts:{}", frame.width, frame.pts);
+            frame_count += 1;
+        }
+    }
+    frame_count += d.drain().count();
 
-    let mut frame = Frame::new();
-    d.dequeue_frame(&mut frame);
-    info!(
-        ">>>>>>>>>> read: {}, width:{}",
-        packet.size, frame.pkt_duration
-    );
+    info!(">>>>>>>>>> decoded {} frames", frame_count);
 }
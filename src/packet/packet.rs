@@ -1,39 +1,174 @@
-use std::ops::{Deref, DerefMut};
-
+use crate::ff_error::*;
 use crate::*;
+use libc::c_int;
+use std::slice;
 
 pub struct Packet {
-    pkt: AVPacket,
+    pkt: *mut AVPacket,
 }
 
 impl Drop for Packet {
     fn drop(&mut self) {
         unsafe {
-            av_packet_unref(&mut self.pkt);
+            av_packet_free(&mut self.pkt);
         }
     }
 }
 
-impl Deref for Packet {
-    type Target = AVPacket;
-
-    fn deref(&self) -> &Self::Target {
-        &self.pkt
+/// the new packet shares the data buffer with this one through reference counting
+impl Clone for Packet {
+    fn clone(&self) -> Self {
+        let pkt = Packet::new();
+        unsafe {
+            if let Err(e) = check(av_packet_ref(pkt.pkt, self.pkt)) {
+                panic!("av_packet_ref failed: {}", e);
+            }
+        }
+        pkt
     }
 }
 
-impl DerefMut for Packet {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.pkt
+impl Default for Packet {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl Packet {
     pub fn new() -> Self {
         unsafe {
-            let mut pkt = std::mem::zeroed();
-            av_init_packet(&mut pkt);
+            let pkt = av_packet_alloc();
+            if pkt.is_null() {
+                panic!("av_packet_alloc failed");
+            }
             Packet { pkt }
         }
     }
+
+    /// a packet holding a copy of bytes, with no timestamps set
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let pkt = Packet::new();
+        unsafe {
+            check(av_new_packet(pkt.pkt, bytes.len() as c_int))?;
+            if !bytes.is_empty() {
+                std::ptr::copy_nonoverlapping(bytes.as_ptr(), (*pkt.pkt).data, bytes.len());
+            }
+        }
+        Ok(pkt)
+    }
+
+    pub fn data(&self) -> &[u8] {
+        unsafe {
+            let pkt = &*self.pkt;
+            if pkt.data.is_null() || pkt.size <= 0 {
+                &[]
+            } else {
+                slice::from_raw_parts(pkt.data, pkt.size as usize)
+            }
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        unsafe { (*self.pkt).size.max(0) as usize }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size() == 0
+    }
+
+    pub fn pts(&self) -> Option<i64> {
+        unsafe { ts_to_option((*self.pkt).pts) }
+    }
+
+    pub fn set_pts(&mut self, pts: Option<i64>) {
+        unsafe { (*self.pkt).pts = pts.unwrap_or(AV_NOPTS_VALUE) }
+    }
+
+    pub fn dts(&self) -> Option<i64> {
+        unsafe { ts_to_option((*self.pkt).dts) }
+    }
+
+    pub fn set_dts(&mut self, dts: Option<i64>) {
+        unsafe { (*self.pkt).dts = dts.unwrap_or(AV_NOPTS_VALUE) }
+    }
+
+    /// None if the duration is unknown
+    pub fn duration(&self) -> Option<i64> {
+        unsafe {
+            match (*self.pkt).duration {
+                0 => None,
+                d => Some(d),
+            }
+        }
+    }
+
+    pub fn set_duration(&mut self, duration: Option<i64>) {
+        unsafe { (*self.pkt).duration = duration.unwrap_or(0) }
+    }
+
+    /// byte position in the input, if known
+    pub fn position(&self) -> Option<i64> {
+        unsafe {
+            match (*self.pkt).pos {
+                -1 => None,
+                pos => Some(pos),
+            }
+        }
+    }
+
+    pub fn set_position(&mut self, pos: Option<i64>) {
+        unsafe { (*self.pkt).pos = pos.unwrap_or(-1) }
+    }
+
+    pub fn stream_index(&self) -> usize {
+        unsafe { (*self.pkt).stream_index as usize }
+    }
+
+    pub fn set_stream_index(&mut self, index: usize) {
+        unsafe { (*self.pkt).stream_index = index as c_int }
+    }
+
+    pub fn is_key(&self) -> bool {
+        unsafe { (*self.pkt).flags & AV_PKT_FLAG_KEY as c_int != 0 }
+    }
+
+    pub fn set_key(&mut self, key: bool) {
+        unsafe {
+            if key {
+                (*self.pkt).flags |= AV_PKT_FLAG_KEY as c_int;
+            } else {
+                (*self.pkt).flags &= !(AV_PKT_FLAG_KEY as c_int);
+            }
+        }
+    }
+
+    pub fn is_corrupt(&self) -> bool {
+        unsafe { (*self.pkt).flags & AV_PKT_FLAG_CORRUPT as c_int != 0 }
+    }
+
+    /// convert pts, dts and duration from src_tb to dst_tb
+    pub fn rescale_ts(&mut self, src_tb: Rational, dst_tb: Rational) {
+        unsafe { av_packet_rescale_ts(self.pkt, src_tb.into(), dst_tb.into()) }
+    }
+
+    /// release the data buffer and reset all fields to their defaults
+    pub fn unref(&mut self) {
+        unsafe { av_packet_unref(self.pkt) }
+    }
+
+    /// move the data buffer and fields into a new packet, self is left blank
+    /// and can be reused
+    pub fn take(&mut self) -> Packet {
+        let packet = Packet::new();
+        unsafe { av_packet_move_ref(packet.pkt, self.pkt) }
+        packet
+    }
+
+    pub fn as_ptr(&self) -> *const AVPacket {
+        self.pkt
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut AVPacket {
+        self.pkt
+    }
 }
@@ -0,0 +1,5 @@
+pub mod resampler;
+pub mod scaler;
+
+pub use self::resampler::Resampler;
+pub use self::scaler::{ScaleAlgorithm, Scaler};
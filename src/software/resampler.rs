@@ -0,0 +1,227 @@
+use crate::ff_error::*;
+use crate::*;
+use libc::{c_int, EINVAL, ENOMEM};
+use log::error;
+use std::{ptr, slice};
+
+/// converts audio frames between sample formats, sample rates and channel
+/// layouts. the resampler buffers samples internally, flush() must be called
+/// at the end of the stream to get the last of them.
+pub struct Resampler {
+    swr: *mut SwrContext,
+    src_format: SampleFormat,
+    src_rate: c_int,
+    src_layout: u64,
+    dst_format: SampleFormat,
+    dst_rate: c_int,
+    dst_layout: u64,
+    src_time_base: Rational,
+}
+
+impl Drop for Resampler {
+    fn drop(&mut self) {
+        unsafe { swr_free(&mut self.swr) }
+    }
+}
+
+impl Resampler {
+    /// sample rates in Hz, channel layouts as AV_CH_LAYOUT_* bitmasks, i.e.
+    /// (Fltp, 44100, AV_CH_LAYOUT_STEREO) to (Flt, 16000, AV_CH_LAYOUT_MONO)
+    pub fn new(
+        src_format: SampleFormat,
+        src_rate: u32,
+        src_layout: u64,
+        dst_format: SampleFormat,
+        dst_rate: u32,
+        dst_layout: u64,
+    ) -> Result<Self> {
+        if src_rate == 0 || dst_rate == 0 || src_layout == 0 || dst_layout == 0 {
+            error!(
+                "invalid resampler config: {}Hz/{:#x} to {}Hz/{:#x}",
+                src_rate, src_layout, dst_rate, dst_layout
+            );
+            return Err(Error::Io(EINVAL));
+        }
+
+        unsafe {
+            let mut swr = swr_alloc_set_opts(
+                ptr::null_mut(),
+                dst_layout as i64,
+                dst_format.into(),
+                dst_rate as c_int,
+                src_layout as i64,
+                src_format.into(),
+                src_rate as c_int,
+                0,
+                ptr::null_mut(),
+            );
+            if swr.is_null() {
+                return Err(Error::Io(ENOMEM));
+            }
+            if let Err(e) = check(swr_init(swr)) {
+                swr_free(&mut swr);
+                return Err(e);
+            }
+
+            Ok(Resampler {
+                swr,
+                src_format,
+                src_rate: src_rate as c_int,
+                src_layout,
+                dst_format,
+                dst_rate: dst_rate as c_int,
+                dst_layout,
+                src_time_base: Rational::new(1, src_rate as i32),
+            })
+        }
+    }
+
+    /// the time base of the pts of the source frames, 1/src_rate by default.
+    /// the pts of the output frames is always in 1/dst_rate.
+    pub fn set_src_time_base(&mut self, time_base: Rational) {
+        self.src_time_base = time_base;
+    }
+
+    pub fn dst_time_base(&self) -> Rational {
+        Rational::new(1, self.dst_rate)
+    }
+
+    /// the number of buffered samples, in the output sample rate
+    pub fn delay(&self) -> i64 {
+        unsafe { swr_get_delay(self.swr, self.dst_rate as i64) }
+    }
+
+    /// stretch or squeeze the output by sample_delta samples, spread over
+    /// distance output samples, to correct clock drift
+    pub fn set_compensation(&mut self, sample_delta: i32, distance: i32) -> Result<()> {
+        unsafe { check(swr_set_compensation(self.swr, sample_delta, distance)).map(|_| ()) }
+    }
+
+    /// the output may have fewer samples than src, or none at all, as some
+    /// are kept in the delay buffer
+    pub fn convert(&mut self, src: &Frame) -> Result<Frame> {
+        if src.format != self.src_format.into()
+            || src.sample_rate != self.src_rate
+            || src.channels != unsafe { av_get_channel_layout_nb_channels(self.src_layout) }
+        {
+            error!(
+                "audio frame(format:{}, rate:{}, channels:{}) doesn't match the resampler({:?}, {}, {:#x})",
+                src.format, src.sample_rate, src.channels, self.src_format, self.src_rate, self.src_layout
+            );
+            return Err(Error::Io(EINVAL));
+        }
+
+        let pts = if src.pts == AV_NOPTS_VALUE {
+            AV_NOPTS_VALUE
+        } else {
+            // swr_next_pts works in 1/(src_rate * dst_rate)
+            let tb = self.src_time_base;
+            let pts = unsafe {
+                av_rescale_rnd(
+                    src.pts,
+                    tb.num as i64 * self.src_rate as i64 * self.dst_rate as i64,
+                    tb.den as i64,
+                    AVRounding_AV_ROUND_NEAR_INF,
+                )
+            };
+            self.next_pts(pts)
+        };
+
+        let mut dst = self.convert_samples(src.extended_data, src.nb_samples)?;
+        dst.pts = pts;
+        Ok(dst)
+    }
+
+    /// the samples left in the delay buffer, None if there are none
+    pub fn flush(&mut self) -> Result<Option<Frame>> {
+        let pts = self.next_pts(i64::MIN);
+        let mut dst = self.convert_samples(ptr::null_mut(), 0)?;
+        if dst.nb_samples == 0 {
+            return Ok(None);
+        }
+        dst.pts = pts;
+        Ok(Some(dst))
+    }
+
+    /// interleaved samples, the output format must be SampleFormat::S16
+    pub fn convert_i16(&mut self, src: &Frame) -> Result<Vec<i16>> {
+        self.check_packed(SampleFormat::S16)?;
+        let dst = self.convert(src)?;
+        Ok(unsafe { packed_samples(&dst) })
+    }
+
+    /// interleaved samples, the output format must be SampleFormat::Flt
+    pub fn convert_f32(&mut self, src: &Frame) -> Result<Vec<f32>> {
+        self.check_packed(SampleFormat::Flt)?;
+        let dst = self.convert(src)?;
+        Ok(unsafe { packed_samples(&dst) })
+    }
+
+    pub fn flush_i16(&mut self) -> Result<Vec<i16>> {
+        self.check_packed(SampleFormat::S16)?;
+        Ok(match self.flush()? {
+            Some(dst) => unsafe { packed_samples(&dst) },
+            None => Vec::new(),
+        })
+    }
+
+    pub fn flush_f32(&mut self) -> Result<Vec<f32>> {
+        self.check_packed(SampleFormat::Flt)?;
+        Ok(match self.flush()? {
+            Some(dst) => unsafe { packed_samples(&dst) },
+            None => Vec::new(),
+        })
+    }
+
+    fn check_packed(&self, format: SampleFormat) -> Result<()> {
+        if self.dst_format != format {
+            error!("resampler outputs {:?}, not {:?}", self.dst_format, format);
+            return Err(Error::Io(EINVAL));
+        }
+        Ok(())
+    }
+
+    // returns the pts in 1/dst_rate
+    fn next_pts(&mut self, pts: i64) -> i64 {
+        unsafe {
+            let pts = swr_next_pts(self.swr, pts);
+            av_rescale_rnd(pts, 1, self.src_rate as i64, AVRounding_AV_ROUND_NEAR_INF)
+        }
+    }
+
+    // converts the nb_samples at data, or drains the delay buffer if data is null
+    fn convert_samples(&mut self, data: *mut *mut u8, nb_samples: c_int) -> Result<Frame> {
+        let mut dst = Frame::new();
+        dst.format = self.dst_format.into();
+        dst.channel_layout = self.dst_layout;
+        dst.channels = unsafe { av_get_channel_layout_nb_channels(self.dst_layout) };
+        dst.sample_rate = self.dst_rate;
+
+        unsafe {
+            let max_samples = check(swr_get_out_samples(self.swr, nb_samples))?;
+            if max_samples == 0 {
+                return Ok(dst);
+            }
+
+            dst.nb_samples = max_samples;
+            check(av_frame_get_buffer(dst.as_mut_ptr(), 0))?;
+            dst.nb_samples = check(swr_convert(
+                self.swr,
+                dst.extended_data,
+                max_samples,
+                data as *mut *const u8,
+                nb_samples,
+            ))?;
+        }
+        Ok(dst)
+    }
+}
+
+// copy the samples of a frame with a packed sample format of type T
+unsafe fn packed_samples<T: Copy>(frame: &Frame) -> Vec<T> {
+    let len = frame.nb_samples as usize * frame.channels as usize;
+    if len == 0 || frame.data[0].is_null() {
+        return Vec::new();
+    }
+    slice::from_raw_parts(frame.data[0] as *const T, len).to_vec()
+}
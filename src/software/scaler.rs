@@ -0,0 +1,313 @@
+use crate::ff_error::*;
+use crate::*;
+use libc::{c_int, EINVAL, ENOMEM};
+use log::error;
+use std::ptr;
+
+/// the SWS_* scaling algorithms
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ScaleAlgorithm {
+    FastBilinear,
+    Bilinear,
+    /// the default of the ffmpeg CLI, a good tradeoff between speed and quality
+    #[default]
+    Bicubic,
+    /// nearest neighbor
+    Point,
+    /// best for downscaling by large factors
+    Area,
+    /// sharpest, and slowest
+    Lanczos,
+}
+
+impl ScaleAlgorithm {
+    fn flags(&self) -> c_int {
+        let flags = match self {
+            ScaleAlgorithm::FastBilinear => SWS_FAST_BILINEAR,
+            ScaleAlgorithm::Bilinear => SWS_BILINEAR,
+            ScaleAlgorithm::Bicubic => SWS_BICUBIC,
+            ScaleAlgorithm::Point => SWS_POINT,
+            ScaleAlgorithm::Area => SWS_AREA,
+            ScaleAlgorithm::Lanczos => SWS_LANCZOS,
+        };
+        flags as c_int
+    }
+}
+
+// colorspace and range of both sides, as passed to sws_setColorspaceDetails
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ColorDetails {
+    src_space: AVColorSpace,
+    src_full_range: bool,
+    dst_space: AVColorSpace,
+    dst_full_range: bool,
+}
+
+// the source properties the context was last configured for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SourceConfig {
+    width: c_int,
+    height: c_int,
+    format: AVPixelFormat,
+    color: ColorDetails,
+}
+
+/// converts video frames between sizes and pixel formats, the source size
+/// and format are taken from each frame so they may change between calls
+pub struct Scaler {
+    ctx: *mut SwsContext,
+    dst_width: u32,
+    dst_height: u32,
+    dst_format: PixelFormat,
+    algorithm: ScaleAlgorithm,
+    // overrides the colorspace and range of the frames
+    color: Option<ColorDetails>,
+    configured: Option<SourceConfig>,
+}
+
+impl Drop for Scaler {
+    fn drop(&mut self) {
+        unsafe { sws_freeContext(self.ctx) }
+    }
+}
+
+impl Scaler {
+    /// a dst_width or dst_height of 0 keeps the size of the source frames
+    pub fn new(
+        dst_width: u32,
+        dst_height: u32,
+        dst_format: PixelFormat,
+        algorithm: ScaleAlgorithm,
+    ) -> Result<Self> {
+        let supported = unsafe { sws_isSupportedOutput(dst_format.into()) } != 0;
+        if !supported {
+            error!("unsupported scaler output format: {:?}", dst_format);
+            return Err(Error::Io(EINVAL));
+        }
+
+        Ok(Scaler {
+            ctx: ptr::null_mut(),
+            dst_width,
+            dst_height,
+            dst_format,
+            algorithm,
+            color: None,
+            configured: None,
+        })
+    }
+
+    /// the colorspace (i.e. AVCOL_SPC_BT709) and range (full or limited) to
+    /// convert from and to. by default the colorspace and range of the source
+    /// frames are used for both sides, BT.601 if unspecified.
+    pub fn set_colorspace(
+        &mut self,
+        src_space: AVColorSpace,
+        src_full_range: bool,
+        dst_space: AVColorSpace,
+        dst_full_range: bool,
+    ) {
+        self.color = Some(ColorDetails {
+            src_space,
+            src_full_range,
+            dst_space,
+            dst_full_range,
+        });
+    }
+
+    pub fn dst_format(&self) -> PixelFormat {
+        self.dst_format
+    }
+
+    /// a new frame with the timestamps and other properties of src
+    pub fn scale(&mut self, src: &Frame) -> Result<Frame> {
+        let mut dst = Frame::new();
+        self.scale_into(src, &mut dst)?;
+        Ok(dst)
+    }
+
+    /// dst gets new buffers unless it already has writable buffers of the
+    /// right size and format
+    pub fn scale_into(&mut self, src: &Frame, dst: &mut Frame) -> Result<()> {
+        let (width, height) = self.dst_size(src);
+        let color = self.configure(src)?;
+        let format: AVPixelFormat = self.dst_format.into();
+
+        let reusable = dst.width == width
+            && dst.height == height
+            && dst.format == format
+            && !dst.data[0].is_null()
+            && dst.is_writable();
+        if !reusable {
+            dst.unref();
+            dst.width = width;
+            dst.height = height;
+            dst.format = format;
+            unsafe { check(av_frame_get_buffer(dst.as_mut_ptr(), 0))? };
+        }
+
+        unsafe {
+            check(av_frame_copy_props(dst.as_mut_ptr(), src.as_ptr()))?;
+            check(sws_scale(
+                self.ctx,
+                src.data.as_ptr() as *const *const u8,
+                src.linesize.as_ptr(),
+                0,
+                src.height,
+                dst.data.as_ptr(),
+                dst.linesize.as_ptr(),
+            ))?;
+        }
+        dst.colorspace = color.dst_space;
+        dst.color_range = if color.dst_full_range {
+            AVColorRange_AVCOL_RANGE_JPEG
+        } else {
+            AVColorRange_AVCOL_RANGE_MPEG
+        };
+        Ok(())
+    }
+
+    /// write into a caller buffer with stride bytes per row, only for packed
+    /// formats such as Rgb24, Rgba or Gray8
+    pub fn scale_to_buffer(&mut self, src: &Frame, dst: &mut [u8], stride: usize) -> Result<()> {
+        self.configure(src)?;
+        let (width, height) = self.dst_size(src);
+        let row_size = self.packed_row_size(width)?;
+        if stride < row_size || dst.len() < stride * (height as usize - 1) + row_size {
+            error!(
+                "scaler buffer too small: {} bytes with stride {} for {}x{} {:?}",
+                dst.len(),
+                stride,
+                width,
+                height,
+                self.dst_format
+            );
+            return Err(Error::Io(EINVAL));
+        }
+
+        let dst_data = [dst.as_mut_ptr()];
+        let dst_stride = [stride as c_int];
+        unsafe {
+            check(sws_scale(
+                self.ctx,
+                src.data.as_ptr() as *const *const u8,
+                src.linesize.as_ptr(),
+                0,
+                src.height,
+                dst_data.as_ptr(),
+                dst_stride.as_ptr(),
+            ))?;
+        }
+        Ok(())
+    }
+
+    /// a tightly packed image, only for packed formats such as Rgb24
+    pub fn scale_to_vec(&mut self, src: &Frame) -> Result<Vec<u8>> {
+        let (width, height) = self.dst_size(src);
+        let row_size = self.packed_row_size(width)?;
+        let mut buf = vec![0; row_size * height as usize];
+        self.scale_to_buffer(src, &mut buf, row_size)?;
+        Ok(buf)
+    }
+
+    fn dst_size(&self, src: &Frame) -> (c_int, c_int) {
+        let width = if self.dst_width > 0 {
+            self.dst_width as c_int
+        } else {
+            src.width
+        };
+        let height = if self.dst_height > 0 {
+            self.dst_height as c_int
+        } else {
+            src.height
+        };
+        (width, height)
+    }
+
+    fn packed_row_size(&self, width: c_int) -> Result<usize> {
+        unsafe {
+            let format = self.dst_format.into();
+            if av_pix_fmt_count_planes(format) != 1 {
+                error!("{:?} is not a packed pixel format", self.dst_format);
+                return Err(Error::Io(EINVAL));
+            }
+            Ok(check(av_image_get_buffer_size(format, width, 1, 1))? as usize)
+        }
+    }
+
+    // (re)create the context if the source frame differs from the last one
+    fn configure(&mut self, src: &Frame) -> Result<ColorDetails> {
+        if src.width <= 0 || src.height <= 0 {
+            error!("invalid scaler source size {}x{}", src.width, src.height);
+            return Err(Error::Io(EINVAL));
+        }
+
+        let src_format: AVPixelFormat = src.format;
+        let color = self.color.unwrap_or_else(|| {
+            let space = match src.colorspace {
+                AVColorSpace_AVCOL_SPC_UNSPECIFIED => AVColorSpace_AVCOL_SPC_BT470BG,
+                space => space,
+            };
+            let full_range = src.color_range == AVColorRange_AVCOL_RANGE_JPEG;
+            ColorDetails {
+                src_space: space,
+                src_full_range: full_range,
+                dst_space: space,
+                dst_full_range: full_range,
+            }
+        });
+        let config = SourceConfig {
+            width: src.width,
+            height: src.height,
+            format: src_format,
+            color,
+        };
+        if self.configured == Some(config) {
+            return Ok(color);
+        }
+
+        let (dst_width, dst_height) = self.dst_size(src);
+        unsafe {
+            if sws_isSupportedInput(src_format) == 0 {
+                error!(
+                    "unsupported scaler input format: {:?}",
+                    PixelFormat::from(src_format)
+                );
+                return Err(Error::Io(EINVAL));
+            }
+
+            self.configured = None;
+            self.ctx = sws_getCachedContext(
+                self.ctx,
+                src.width,
+                src.height,
+                src_format,
+                dst_width,
+                dst_height,
+                self.dst_format.into(),
+                self.algorithm.flags(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null(),
+            );
+            if self.ctx.is_null() {
+                return Err(Error::Io(ENOMEM));
+            }
+
+            // fails for formats without colorspace conversion (i.e. gray),
+            // the defaults of the context are then used
+            sws_setColorspaceDetails(
+                self.ctx,
+                sws_getCoefficients(color.src_space as c_int),
+                color.src_full_range as c_int,
+                sws_getCoefficients(color.dst_space as c_int),
+                color.dst_full_range as c_int,
+                0,
+                1 << 16,
+                1 << 16,
+            );
+        }
+
+        self.configured = Some(config);
+        Ok(color)
+    }
+}
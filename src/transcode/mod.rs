@@ -0,0 +1,3 @@
+pub mod transcoder;
+
+pub use self::transcoder::{EncodeSpec, Progress, StreamSpec, Transcoder};
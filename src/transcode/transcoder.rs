@@ -0,0 +1,702 @@
+use crate::filter::Graph;
+use crate::format::remux::{discard_output, required_bsf, unsupported_reason};
+use crate::*;
+use libc::EINVAL;
+use log::{error, info, warn};
+use std::time::{Duration, Instant};
+
+// how often the progress callback is called at most
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+type ProgressCallback = Box<dyn FnMut(&Progress)>;
+
+/// what to do with a stream of the input
+#[derive(Debug, Clone)]
+pub enum StreamSpec {
+    /// copy the packets without re-encoding
+    Copy,
+    /// decode, filter and encode again
+    Encode(EncodeSpec),
+    /// leave the stream out of the output
+    Skip,
+}
+
+/// the encoder of a re-encoded stream and the conversions before it, the
+/// properties that are not set are kept from the input stream
+#[derive(Debug, Clone)]
+pub struct EncodeSpec {
+    encoder: String,
+    bit_rate: i64,
+    width: Option<u32>,
+    height: Option<u32>,
+    frame_rate: Option<Rational>,
+    pixel_format: Option<PixelFormat>,
+    sample_rate: Option<u32>,
+    channel_layout: Option<u64>,
+    sample_format: Option<SampleFormat>,
+    filter: Option<String>,
+    options: Dictionary,
+}
+
+impl EncodeSpec {
+    /// i.e. "libx264", "aac", "libmp3lame"
+    pub fn new(encoder: &str) -> Self {
+        EncodeSpec {
+            encoder: encoder.to_owned(),
+            bit_rate: 0,
+            width: None,
+            height: None,
+            frame_rate: None,
+            pixel_format: None,
+            sample_rate: None,
+            channel_layout: None,
+            sample_format: None,
+            filter: None,
+            options: Dictionary::new(),
+        }
+    }
+
+    /// in bits per second
+    pub fn bit_rate(mut self, bit_rate: i64) -> Self {
+        self.bit_rate = bit_rate;
+        self
+    }
+
+    /// video only, a width or height of 0 is derived from the other one,
+    /// keeping the aspect ratio
+    pub fn size(mut self, width: u32, height: u32) -> Self {
+        self.width = Some(width).filter(|w| *w > 0);
+        self.height = Some(height).filter(|h| *h > 0);
+        self
+    }
+
+    /// video only, frames are dropped or duplicated to get a constant rate,
+    /// the average frame rate of the input by default
+    pub fn frame_rate(mut self, frame_rate: Rational) -> Self {
+        self.frame_rate = Some(frame_rate);
+        self
+    }
+
+    /// video only, by default the format of the input if the encoder
+    /// supports it, or the closest one it does
+    pub fn pixel_format(mut self, pixel_format: PixelFormat) -> Self {
+        self.pixel_format = Some(pixel_format);
+        self
+    }
+
+    /// audio only
+    pub fn sample_rate(mut self, sample_rate: u32) -> Self {
+        self.sample_rate = Some(sample_rate);
+        self
+    }
+
+    /// audio only, AV_CH_LAYOUT_* bitmask
+    pub fn channel_layout(mut self, channel_layout: u64) -> Self {
+        self.channel_layout = Some(channel_layout);
+        self
+    }
+
+    /// audio only, by default the format of the input if the encoder
+    /// supports it, or the closest one it does
+    pub fn sample_format(mut self, sample_format: SampleFormat) -> Self {
+        self.sample_format = Some(sample_format);
+        self
+    }
+
+    /// filters applied before the conversions above, in the syntax of the
+    /// ffmpeg CLI, i.e. "hflip,drawtext=text=hello" or "volume=0.5"
+    pub fn filter(mut self, filter: &str) -> Self {
+        self.filter = Some(filter.to_owned());
+        self
+    }
+
+    /// generic or codec-private encoder option, i.e. ("preset", "veryfast")
+    pub fn option(mut self, key: &str, value: &str) -> Result<Self> {
+        self.options.insert(key, value)?;
+        Ok(self)
+    }
+}
+
+/// the state of a transcoding, passed to the progress callback
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Progress {
+    /// seconds of output written
+    pub time: f64,
+    /// duration of the input in seconds, if known
+    pub duration: Option<f64>,
+    /// video frames encoded
+    pub frames: u64,
+    /// video frames encoded per second
+    pub fps: f64,
+    /// seconds of output per second, 1.0 is real time
+    pub speed: f64,
+    pub elapsed: Duration,
+}
+
+/// decodes, filters and encodes again the streams of an input into a new
+/// file, or copies them, as described by a StreamSpec per stream
+pub struct Transcoder {
+    input: Input,
+    output_path: String,
+    format: Option<String>,
+    stream_specs: Vec<(usize, StreamSpec)>,
+    media_type_specs: Vec<(MediaType, StreamSpec)>,
+    muxer_options: Dictionary,
+    on_progress: Option<ProgressCallback>,
+}
+
+impl Transcoder {
+    /// streams without a spec, set with stream() or media_type(), are skipped
+    pub fn new(input: Input, output_path: &str) -> Self {
+        Transcoder {
+            input,
+            output_path: output_path.to_owned(),
+            format: None,
+            stream_specs: Vec::new(),
+            media_type_specs: Vec::new(),
+            muxer_options: Dictionary::new(),
+            on_progress: None,
+        }
+    }
+
+    /// force a muxer, i.e. "mp4", instead of guessing it from the output path
+    pub fn format(mut self, format: &str) -> Self {
+        self.format = Some(format.to_owned());
+        self
+    }
+
+    /// the spec of the stream at index of the input, takes precedence over
+    /// the spec of its media type
+    pub fn stream(mut self, index: usize, spec: StreamSpec) -> Self {
+        self.stream_specs.push((index, spec));
+        self
+    }
+
+    /// the spec of the streams of media_type, attached pictures excluded
+    pub fn media_type(mut self, media_type: MediaType, spec: StreamSpec) -> Self {
+        self.media_type_specs.push((media_type, spec));
+        self
+    }
+
+    /// generic or muxer-private option, i.e. ("movflags", "+faststart")
+    pub fn muxer_option(mut self, key: &str, value: &str) -> Result<Self> {
+        self.muxer_options.insert(key, value)?;
+        Ok(self)
+    }
+
+    /// called every 500ms at most, and once when done
+    pub fn on_progress<F: FnMut(&Progress) + 'static>(mut self, on_progress: F) -> Self {
+        self.on_progress = Some(Box::new(on_progress));
+        self
+    }
+
+    /// transcode the whole input, every stage is flushed at the end of the
+    /// input. returns the final progress. the output file is removed if the
+    /// transcoding fails.
+    pub fn run(mut self) -> Result<Progress> {
+        let skipped_all = self
+            .input
+            .streams()
+            .all(|stream| matches!(self.spec_of(&stream), StreamSpec::Skip));
+        if skipped_all {
+            error!("no streams to transcode into {}", self.output_path);
+            return Err(Error::Io(EINVAL));
+        }
+
+        let mut output = Output::with_format(&self.output_path, self.format.as_deref())?;
+        // copied streams get the bitstream filters they need in CopyState
+        output.set_auto_bitstream_filters(false);
+        let result = self.transcode(&mut output);
+        if result.is_err() {
+            discard_output(output, &self.output_path);
+        }
+        result
+    }
+
+    fn transcode(&mut self, output: &mut Output) -> Result<Progress> {
+        let mut streams = Vec::new();
+        for stream in self.input.streams() {
+            let state = match self.spec_of(&stream) {
+                StreamSpec::Skip => None,
+                StreamSpec::Copy => Some(StreamState::Copy(CopyState::new(stream, output)?)),
+                StreamSpec::Encode(spec) => Some(StreamState::Encode(Box::new(EncodeState::new(
+                    stream, spec, output,
+                )?))),
+            };
+            streams.push(state);
+        }
+
+        output.set_metadata(&self.input.metadata())?;
+        output.write_header_with_options(&self.muxer_options)?;
+
+        // subtracted from all timestamps so that the output starts at 0
+        let start_time = match self.input.start_time {
+            AV_NOPTS_VALUE => 0,
+            start_time => start_time,
+        };
+        let mut progress = ProgressTracker::new(self.input.duration(), self.on_progress.take());
+
+        for item in self.input.packets() {
+            let (stream, mut packet) = item?;
+            let state = match streams.get_mut(stream.index()) {
+                Some(Some(state)) => state,
+                _ => continue,
+            };
+
+            let shift = av_time_base().rescale(start_time, stream.time_base());
+            match state {
+                StreamState::Copy(st) => {
+                    packet.set_pts(packet.pts().map(|pts| pts - shift));
+                    packet.set_dts(packet.dts().map(|dts| dts - shift));
+                    packet.set_position(None);
+                    st.write(&packet, output, &mut progress)?;
+                }
+                StreamState::Encode(st) => {
+                    // a packet the decoder can't take yet is resent by decode()
+                    // once the pending frames are received
+                    let mut frames = Vec::new();
+                    for frame in st.decoder.decode(&packet) {
+                        match frame {
+                            Ok(frame) => frames.push(frame),
+                            Err(Error::InvalidData) => {
+                                warn!("dropping corrupt packet of stream {}", stream.index());
+                            }
+                            Err(e) => return Err(e),
+                        }
+                    }
+                    for mut frame in frames {
+                        set_frame_pts(&mut frame, shift);
+                        st.filter(Some(&frame), output, &mut progress)?;
+                    }
+                }
+            }
+            progress.report(false);
+        }
+
+        for state in streams.iter_mut().flatten() {
+            match state {
+                StreamState::Copy(st) => st.flush(output, &mut progress)?,
+                StreamState::Encode(st) => {
+                    let frames = st.decoder.drain().collect::<Result<Vec<_>>>()?;
+                    let shift = av_time_base().rescale(start_time, st.in_time_base);
+                    for mut frame in frames {
+                        set_frame_pts(&mut frame, shift);
+                        st.filter(Some(&frame), output, &mut progress)?;
+                    }
+                    st.filter(None, output, &mut progress)?;
+                }
+            }
+        }
+        output.write_trailer()?;
+
+        let result = progress.report(true);
+        info!(
+            "transcoded {:.2}s into {}, {} video frames, speed {:.2}x",
+            result.time, self.output_path, result.frames, result.speed
+        );
+        Ok(result)
+    }
+
+    fn spec_of(&self, stream: &Stream) -> StreamSpec {
+        if let Some((_, spec)) = self.stream_specs.iter().find(|(i, _)| *i == stream.index()) {
+            return spec.clone();
+        }
+        if stream.is_attached_picture() {
+            return StreamSpec::Skip;
+        }
+        self.media_type_specs
+            .iter()
+            .find(|(media_type, _)| *media_type == stream.media_type())
+            .map_or(StreamSpec::Skip, |(_, spec)| spec.clone())
+    }
+}
+
+enum StreamState {
+    Copy(CopyState),
+    Encode(Box<EncodeState>),
+}
+
+struct CopyState {
+    out_index: usize,
+    time_base: Rational,
+    bsf: Option<BitstreamFilter>,
+}
+
+impl CopyState {
+    fn new(stream: Stream, output: &mut Output) -> Result<Self> {
+        let params = stream.codec_parameters();
+        // the muxer would only fail on it in write_header()
+        if let Some(reason) = unsupported_reason(output.output_format(), params.codec_id()) {
+            error!("can't copy stream {}: {}", stream.index(), reason);
+            return Err(Error::Io(EINVAL));
+        }
+        let bsf = match required_bsf(&params, output.format_name()) {
+            Some(name) => Some(BitstreamFilter::with_stream(name, stream)?),
+            None => None,
+        };
+        let (out_index, time_base) = match &bsf {
+            Some(bsf) => (
+                output.add_stream(&bsf.output_parameters(), bsf.output_time_base())?,
+                bsf.output_time_base(),
+            ),
+            None => (
+                output.add_stream(&params, stream.time_base())?,
+                stream.time_base(),
+            ),
+        };
+        output.set_stream_metadata(out_index, &stream.metadata())?;
+        output.set_stream_disposition(out_index, stream.disposition())?;
+
+        Ok(CopyState {
+            out_index,
+            time_base,
+            bsf,
+        })
+    }
+
+    fn write(
+        &mut self,
+        packet: &Packet,
+        output: &mut Output,
+        progress: &mut ProgressTracker,
+    ) -> Result<()> {
+        let packets = match self.bsf.as_mut() {
+            Some(bsf) => bsf.filter(packet)?,
+            None => vec![packet.clone()],
+        };
+        for packet in packets {
+            self.write_packet(packet, output, progress)?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self, output: &mut Output, progress: &mut ProgressTracker) -> Result<()> {
+        if let Some(bsf) = self.bsf.as_mut() {
+            for packet in bsf.drain()? {
+                self.write_packet(packet, output, progress)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_packet(
+        &self,
+        mut packet: Packet,
+        output: &mut Output,
+        progress: &mut ProgressTracker,
+    ) -> Result<()> {
+        packet.set_stream_index(self.out_index);
+        progress.packet_written(&packet, self.time_base);
+        output.write_interleaved_packet(&mut packet)
+    }
+}
+
+struct EncodeState {
+    decoder: Decoder,
+    in_time_base: Rational,
+    graph: Graph,
+    encoder: Encoder,
+    out_index: usize,
+    is_video: bool,
+}
+
+impl EncodeState {
+    fn new(stream: Stream, spec: EncodeSpec, output: &mut Output) -> Result<Self> {
+        let decoder = Decoder::with_stream(stream)?;
+        let mut builder = EncoderBuilder::with_name(&spec.encoder)?
+            .bit_rate(spec.bit_rate)
+            .global_header(output.needs_global_header())
+            .options(spec.options.clone());
+
+        let is_video = stream.is_video_stream();
+        let mut graph = Graph::new()?;
+        graph.add_input_from_decoder("in", &decoder)?;
+        if is_video {
+            // without it the frames keep their timestamps, and the time base
+            // of the stream is no frame rate
+            let frame_rate = video_frame_rate(&spec, stream);
+            if frame_rate.is_none() {
+                warn!("unknown frame rate of stream {}", stream.index());
+            }
+            graph.add_video_output("out")?;
+            graph.parse(&video_filters(&spec, frame_rate, &builder))?;
+
+            let (width, height) = graph.output_size("out")?;
+            builder = builder
+                .size(width, height)
+                .pixel_format(graph.output_pixel_format("out")?)
+                .time_base(graph.output_time_base("out")?);
+            if let Some(frame_rate) = frame_rate {
+                builder = builder.frame_rate(frame_rate);
+            }
+            let sar = graph.output_sample_aspect_ratio("out")?;
+            if sar.is_valid() {
+                builder = builder.sample_aspect_ratio(sar);
+            }
+        } else if stream.is_audio_stream() {
+            graph.add_audio_output("out")?;
+            graph.parse(&audio_filters(&spec, &decoder, &builder))?;
+
+            builder = builder
+                .sample_rate(graph.output_sample_rate("out")?)
+                .channel_layout(graph.output_channel_layout("out")?)
+                .sample_format(graph.output_sample_format("out")?)
+                .time_base(graph.output_time_base("out")?);
+        } else {
+            error!(
+                "can't re-encode {} stream {}",
+                stream.media_type().name(),
+                stream.index()
+            );
+            return Err(Error::Io(EINVAL));
+        }
+
+        let encoder = builder.build()?;
+        let out_index = output.add_stream_from_encoder(&encoder)?;
+        output.set_stream_metadata(out_index, &stream.metadata())?;
+        output.set_stream_disposition(out_index, stream.disposition())?;
+
+        Ok(EncodeState {
+            decoder,
+            in_time_base: stream.time_base(),
+            graph,
+            encoder,
+            out_index,
+            is_video,
+        })
+    }
+
+    /// push a decoded frame through the graph and encode what comes out,
+    /// None flushes the graph and the encoder
+    fn filter(
+        &mut self,
+        frame: Option<&Frame>,
+        output: &mut Output,
+        progress: &mut ProgressTracker,
+    ) -> Result<()> {
+        match frame {
+            Some(frame) => self.graph.push("in", frame)?,
+            None => self.graph.push_eof("in")?,
+        }
+
+        loop {
+            let mut filtered = Frame::new();
+            match self.graph.pull("out", &mut filtered) {
+                Ok(()) => {}
+                Err(Error::Again) | Err(Error::Eof) => break,
+                Err(e) => return Err(e),
+            }
+            if self.is_video {
+                // let the encoder decide on the frame types
+                filtered.pict_type = AVPictureType_AV_PICTURE_TYPE_NONE;
+                progress.frames += 1;
+            }
+            self.encode(Some(&filtered), output, progress)?;
+        }
+
+        if frame.is_none() {
+            self.encode(None, output, progress)?;
+        }
+        Ok(())
+    }
+
+    /// None flushes the encoder
+    fn encode(
+        &mut self,
+        frame: Option<&Frame>,
+        output: &mut Output,
+        progress: &mut ProgressTracker,
+    ) -> Result<()> {
+        loop {
+            let ret = match frame {
+                Some(frame) => self.encoder.send_frame(frame),
+                None => self.encoder.flush(),
+            };
+            match ret {
+                Ok(()) => break,
+                // the encoder is full, make room and resend
+                Err(Error::Again) => self.receive_packets(output, progress)?,
+                // already flushed
+                Err(Error::Eof) if frame.is_none() => break,
+                Err(e) => return Err(e),
+            }
+        }
+        self.receive_packets(output, progress)
+    }
+
+    fn receive_packets(
+        &mut self,
+        output: &mut Output,
+        progress: &mut ProgressTracker,
+    ) -> Result<()> {
+        loop {
+            let mut packet = Packet::new();
+            match self.encoder.receive_packet(&mut packet) {
+                Ok(()) => {
+                    packet.set_stream_index(self.out_index);
+                    progress.packet_written(&packet, self.encoder.time_base());
+                    output.write_interleaved_packet(&mut packet)?;
+                }
+                Err(Error::Again) | Err(Error::Eof) => return Ok(()),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+// the frame rate of the encoded stream, the one of the spec or the average
+// frame rate of the input, None if neither is known
+fn video_frame_rate(spec: &EncodeSpec, stream: Stream) -> Option<Rational> {
+    [
+        spec.frame_rate,
+        Some(stream.avg_frame_rate()),
+        Some(stream.r_frame_rate()),
+    ]
+    .iter()
+    .flatten()
+    .find(|rate| rate.is_valid())
+    .copied()
+}
+
+// the user filters, then scale, fps and format for the encoder
+fn video_filters(
+    spec: &EncodeSpec,
+    frame_rate: Option<Rational>,
+    builder: &EncoderBuilder,
+) -> String {
+    let mut filters: Vec<String> = spec.filter.iter().cloned().collect();
+
+    if spec.width.is_some() || spec.height.is_some() {
+        // -2 keeps the aspect ratio with an even size, as required by yuv420p
+        filters.push(format!(
+            "scale={}:{}",
+            spec.width.map_or(-2, |w| w as i64),
+            spec.height.map_or(-2, |h| h as i64)
+        ));
+    }
+    if let Some(rate) = frame_rate {
+        filters.push(format!("fps={}", rate));
+    }
+
+    let pixel_formats = match spec.pixel_format {
+        Some(pixel_format) => vec![pixel_format],
+        None => builder.supported_pixel_formats(),
+    };
+    let names: Vec<&str> = pixel_formats.iter().filter_map(|f| f.name()).collect();
+    if !names.is_empty() {
+        filters.push(format!("format=pix_fmts={}", names.join("|")));
+    }
+
+    if filters.is_empty() {
+        "null".to_owned()
+    } else {
+        filters.join(",")
+    }
+}
+
+// the user filters, then aresample and aformat for the encoder
+fn audio_filters(spec: &EncodeSpec, decoder: &Decoder, builder: &EncoderBuilder) -> String {
+    let mut filters: Vec<String> = spec.filter.iter().cloned().collect();
+
+    // aresample also retimes the frames in 1/sample_rate
+    let sample_rate = match spec.sample_rate {
+        Some(rate) => rate,
+        None => {
+            let supported = builder.supported_sample_rates();
+            let rate = decoder.sample_rate as u32;
+            if supported.is_empty() || supported.contains(&rate) {
+                rate
+            } else {
+                supported[0]
+            }
+        }
+    };
+    filters.push(format!("aresample={}", sample_rate));
+
+    let mut aformat = Vec::new();
+    let sample_formats = match spec.sample_format {
+        Some(sample_format) => vec![sample_format],
+        None => builder.supported_sample_formats(),
+    };
+    let names: Vec<&str> = sample_formats.iter().filter_map(|f| f.name()).collect();
+    if !names.is_empty() {
+        aformat.push(format!("sample_fmts={}", names.join("|")));
+    }
+    if let Some(channel_layout) = spec.channel_layout {
+        aformat.push(format!("channel_layouts={:#x}", channel_layout));
+    }
+    if !aformat.is_empty() {
+        filters.push(format!("aformat={}", aformat.join(":")));
+    }
+
+    filters.join(",")
+}
+
+// frames without pts are timestamped by the decoder
+fn set_frame_pts(frame: &mut Frame, shift: i64) {
+    frame.pts = match frame.best_effort_timestamp {
+        AV_NOPTS_VALUE => AV_NOPTS_VALUE,
+        pts => pts - shift,
+    };
+}
+
+fn av_time_base() -> Rational {
+    Rational::new(1, AV_TIME_BASE as i32)
+}
+
+struct ProgressTracker {
+    started: Instant,
+    last_report: Instant,
+    progress: Progress,
+    frames: u64,
+    on_progress: Option<ProgressCallback>,
+}
+
+impl ProgressTracker {
+    fn new(duration: Option<f64>, on_progress: Option<ProgressCallback>) -> Self {
+        let now = Instant::now();
+        ProgressTracker {
+            started: now,
+            last_report: now,
+            progress: Progress {
+                duration,
+                ..Progress::default()
+            },
+            frames: 0,
+            on_progress,
+        }
+    }
+
+    fn packet_written(&mut self, packet: &Packet, time_base: Rational) {
+        if let Some(ts) = packet.pts().or_else(|| packet.dts()) {
+            let time = ts as f64 * time_base.as_f64();
+            if time > self.progress.time {
+                self.progress.time = time;
+            }
+        }
+    }
+
+    // calls the callback if forced or if it has not been for a while
+    fn report(&mut self, force: bool) -> Progress {
+        let now = Instant::now();
+        if !force && now.duration_since(self.last_report) < PROGRESS_INTERVAL {
+            return self.progress;
+        }
+        self.last_report = now;
+
+        let elapsed = now.duration_since(self.started);
+        let secs = elapsed.as_secs_f64();
+        let progress = &mut self.progress;
+        progress.frames = self.frames;
+        progress.elapsed = elapsed;
+        if secs > 0.0 {
+            progress.fps = self.frames as f64 / secs;
+            progress.speed = progress.time / secs;
+        }
+
+        if let Some(on_progress) = self.on_progress.as_mut() {
+            on_progress(&self.progress);
+        }
+        self.progress
+    }
+}
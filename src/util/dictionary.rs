@@ -0,0 +1,357 @@
+use crate::ff_error::*;
+use crate::*;
+use libc::c_int;
+use log::error;
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::marker::PhantomData;
+use std::ptr;
+
+/// the AV_DICT_* flags
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct DictionaryFlags(c_int);
+
+impl DictionaryFlags {
+    pub const NONE: DictionaryFlags = DictionaryFlags(0);
+    /// keys are matched case-insensitively by default
+    pub const MATCH_CASE: DictionaryFlags = DictionaryFlags(AV_DICT_MATCH_CASE as c_int);
+    /// get() returns the first entry whose key starts with the given key
+    pub const IGNORE_SUFFIX: DictionaryFlags = DictionaryFlags(AV_DICT_IGNORE_SUFFIX as c_int);
+    /// don't replace the value of an existing entry
+    pub const DONT_OVERWRITE: DictionaryFlags = DictionaryFlags(AV_DICT_DONT_OVERWRITE as c_int);
+    /// append to the value of an existing entry instead of replacing it
+    pub const APPEND: DictionaryFlags = DictionaryFlags(AV_DICT_APPEND as c_int);
+    /// allow more than one entry with the same key
+    pub const MULTIKEY: DictionaryFlags = DictionaryFlags(AV_DICT_MULTIKEY as c_int);
+
+    pub const fn bits(&self) -> c_int {
+        self.0
+    }
+}
+
+impl std::ops::BitOr for DictionaryFlags {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        DictionaryFlags(self.0 | rhs.0)
+    }
+}
+
+/// an owned AVDictionary, used for options and metadata
+///
+/// functions taking options, such as avformat_open_input and avcodec_open2,
+/// replace the dictionary with one that holds the entries that were not
+/// consumed, which as_mut_ptr_ref() allows.
+pub struct Dictionary {
+    dict: *mut AVDictionary,
+}
+
+impl Drop for Dictionary {
+    fn drop(&mut self) {
+        unsafe { av_dict_free(&mut self.dict) }
+    }
+}
+
+impl Clone for Dictionary {
+    fn clone(&self) -> Self {
+        unsafe { Dictionary::copy_from(self.dict) }
+    }
+}
+
+impl Default for Dictionary {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for Dictionary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl Dictionary {
+    pub fn new() -> Self {
+        Dictionary {
+            dict: ptr::null_mut(),
+        }
+    }
+
+    /// parse "key1=value1:key2=value2" style strings, with key_val_sep and
+    /// pairs_sep being the sets of characters separating keys from values and
+    /// pairs from each other, i.e. Dictionary::parse(s, "=", ":")
+    pub fn parse(s: &str, key_val_sep: &str, pairs_sep: &str) -> Result<Self> {
+        let s = CString::new(s)?;
+        let key_val_sep = CString::new(key_val_sep)?;
+        let pairs_sep = CString::new(pairs_sep)?;
+
+        let mut dict = Dictionary::new();
+        unsafe {
+            check(av_dict_parse_string(
+                &mut dict.dict,
+                s.as_ptr(),
+                key_val_sep.as_ptr(),
+                pairs_sep.as_ptr(),
+                0,
+            ))?;
+        }
+        Ok(dict)
+    }
+
+    pub fn insert(&mut self, key: &str, value: &str) -> Result<()> {
+        self.insert_with_flags(key, value, DictionaryFlags::NONE)
+    }
+
+    pub fn insert_with_flags(
+        &mut self,
+        key: &str,
+        value: &str,
+        flags: DictionaryFlags,
+    ) -> Result<()> {
+        let key = CString::new(key)?;
+        let value = CString::new(value)?;
+        unsafe {
+            check(av_dict_set(
+                &mut self.dict,
+                key.as_ptr(),
+                value.as_ptr(),
+                flags.bits(),
+            ))
+            .map(|_| ())
+        }
+    }
+
+    /// removes all the entries with the key
+    pub fn remove(&mut self, key: &str) -> Result<()> {
+        let key = CString::new(key)?;
+        unsafe {
+            while !av_dict_get(self.dict, key.as_ptr(), ptr::null(), 0).is_null() {
+                check(av_dict_set(&mut self.dict, key.as_ptr(), ptr::null(), 0))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// case-insensitive lookup of the first entry with the key
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.get_with_flags(key, DictionaryFlags::NONE)
+    }
+
+    pub fn get_with_flags(&self, key: &str, flags: DictionaryFlags) -> Option<&str> {
+        let key = CString::new(key).ok()?;
+        unsafe {
+            let entry = av_dict_get(self.dict, key.as_ptr(), ptr::null(), flags.bits());
+            if entry.is_null() {
+                None
+            } else {
+                CStr::from_ptr((*entry).value).to_str().ok()
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        unsafe { av_dict_count(self.dict).max(0) as usize }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// entries in insertion order, entries that are not valid UTF-8 are skipped
+    pub fn iter(&self) -> DictionaryIter<'_> {
+        DictionaryIter {
+            dict: self.dict,
+            entry: ptr::null(),
+            empty_key: CString::default(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// entries with duplicate keys are collapsed, the last one wins
+    pub fn to_map(&self) -> HashMap<String, String> {
+        self.iter()
+            .map(|(k, v)| (k.to_owned(), v.to_owned()))
+            .collect()
+    }
+
+    /// a copy of a dictionary owned by libav, i.e. AVStream.metadata
+    pub(crate) unsafe fn copy_from(src: *const AVDictionary) -> Self {
+        let mut dict = Dictionary::new();
+        if let Err(e) = check(av_dict_copy(&mut dict.dict, src, 0)) {
+            panic!("av_dict_copy failed: {}", e);
+        }
+        dict
+    }
+
+    pub fn as_ptr(&self) -> *const AVDictionary {
+        self.dict
+    }
+
+    /// for the libav functions that take an AVDictionary** and replace it
+    pub fn as_mut_ptr_ref(&mut self) -> &mut *mut AVDictionary {
+        &mut self.dict
+    }
+}
+
+impl<K: AsRef<str>, V: AsRef<str>> FromIterator<(K, V)> for Dictionary {
+    /// pairs containing a nul character are skipped
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        let mut dict = Dictionary::new();
+        for (key, value) in iter {
+            if let Err(e) = dict.insert(key.as_ref(), value.as_ref()) {
+                error!("failed to insert {:?} into Dictionary: {}", key.as_ref(), e);
+            }
+        }
+        dict
+    }
+}
+
+impl<K: AsRef<str>, V: AsRef<str>> From<HashMap<K, V>> for Dictionary {
+    fn from(map: HashMap<K, V>) -> Self {
+        map.into_iter().collect()
+    }
+}
+
+impl<'a> IntoIterator for &'a Dictionary {
+    type Item = (&'a str, &'a str);
+    type IntoIter = DictionaryIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct DictionaryIter<'a> {
+    dict: *const AVDictionary,
+    entry: *const AVDictionaryEntry,
+    empty_key: CString,
+    _marker: PhantomData<&'a Dictionary>,
+}
+
+impl<'a> Iterator for DictionaryIter<'a> {
+    type Item = (&'a str, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        unsafe {
+            loop {
+                // an empty key with AV_DICT_IGNORE_SUFFIX matches every entry
+                self.entry = av_dict_get(
+                    self.dict,
+                    self.empty_key.as_ptr(),
+                    self.entry,
+                    AV_DICT_IGNORE_SUFFIX as c_int,
+                );
+                if self.entry.is_null() {
+                    return None;
+                }
+
+                let key = CStr::from_ptr((*self.entry).key).to_str();
+                let value = CStr::from_ptr((*self.entry).value).to_str();
+                if let (Ok(key), Ok(value)) = (key, value) {
+                    return Some((key, value));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_and_get() {
+        let mut dict = Dictionary::new();
+        assert!(dict.is_empty());
+        dict.insert("title", "a").unwrap();
+        dict.insert("artist", "b").unwrap();
+        assert_eq!(dict.len(), 2);
+        assert_eq!(dict.get("title"), Some("a"));
+        assert_eq!(dict.get("TITLE"), Some("a"));
+        assert_eq!(
+            dict.get_with_flags("TITLE", DictionaryFlags::MATCH_CASE),
+            None
+        );
+        assert_eq!(dict.get("album"), None);
+
+        dict.insert("title", "c").unwrap();
+        assert_eq!(dict.get("title"), Some("c"));
+        assert_eq!(dict.len(), 2);
+    }
+
+    #[test]
+    fn insert_flags() {
+        let mut dict = Dictionary::new();
+        dict.insert("key", "a").unwrap();
+        dict.insert_with_flags("key", "b", DictionaryFlags::DONT_OVERWRITE)
+            .unwrap();
+        assert_eq!(dict.get("key"), Some("a"));
+        dict.insert_with_flags("key", "b", DictionaryFlags::APPEND)
+            .unwrap();
+        assert_eq!(dict.get("key"), Some("ab"));
+        dict.insert_with_flags("key", "c", DictionaryFlags::MULTIKEY)
+            .unwrap();
+        assert_eq!(dict.len(), 2);
+    }
+
+    #[test]
+    fn remove() {
+        let mut dict = Dictionary::new();
+        dict.insert_with_flags("key", "a", DictionaryFlags::MULTIKEY)
+            .unwrap();
+        dict.insert_with_flags("key", "b", DictionaryFlags::MULTIKEY)
+            .unwrap();
+        dict.insert("other", "c").unwrap();
+        dict.remove("key").unwrap();
+        assert_eq!(dict.get("key"), None);
+        assert_eq!(dict.len(), 1);
+        // removing a missing key is not an error
+        dict.remove("key").unwrap();
+    }
+
+    #[test]
+    fn parse() {
+        let dict = Dictionary::parse("preset=fast:crf=23", "=", ":").unwrap();
+        assert_eq!(dict.get("preset"), Some("fast"));
+        assert_eq!(dict.get("crf"), Some("23"));
+        assert!(Dictionary::parse("preset", "=", ":").is_err());
+    }
+
+    #[test]
+    fn iteration_keeps_insertion_order() {
+        let dict: Dictionary = vec![("b", "1"), ("a", "2"), ("c", "3")]
+            .into_iter()
+            .collect();
+        let entries: Vec<(&str, &str)> = dict.iter().collect();
+        assert_eq!(entries, vec![("b", "1"), ("a", "2"), ("c", "3")]);
+
+        let map = dict.to_map();
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.get("a").map(String::as_str), Some("2"));
+    }
+
+    #[test]
+    fn clone_is_independent() {
+        let mut dict = Dictionary::new();
+        dict.insert("key", "a").unwrap();
+        let copy = dict.clone();
+        dict.insert("key", "b").unwrap();
+        assert_eq!(copy.get("key"), Some("a"));
+    }
+
+    #[test]
+    fn nul_in_key_is_rejected() {
+        let mut dict = Dictionary::new();
+        assert!(dict.insert("k\0ey", "a").is_err());
+    }
+
+    #[test]
+    fn flags_combine() {
+        let flags = DictionaryFlags::MATCH_CASE | DictionaryFlags::APPEND;
+        assert_eq!(
+            flags.bits(),
+            DictionaryFlags::MATCH_CASE.bits() | DictionaryFlags::APPEND.bits()
+        );
+        assert_eq!(DictionaryFlags::default(), DictionaryFlags::NONE);
+    }
+}
@@ -0,0 +1,77 @@
+use crate::*;
+use std::ffi::CStr;
+
+/// the AVMEDIA_TYPE_* values
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MediaType {
+    Unknown,
+    Video,
+    Audio,
+    Data,
+    Subtitle,
+    Attachment,
+}
+
+impl From<AVMediaType> for MediaType {
+    fn from(value: AVMediaType) -> Self {
+        match value {
+            AVMediaType_AVMEDIA_TYPE_VIDEO => MediaType::Video,
+            AVMediaType_AVMEDIA_TYPE_AUDIO => MediaType::Audio,
+            AVMediaType_AVMEDIA_TYPE_DATA => MediaType::Data,
+            AVMediaType_AVMEDIA_TYPE_SUBTITLE => MediaType::Subtitle,
+            AVMediaType_AVMEDIA_TYPE_ATTACHMENT => MediaType::Attachment,
+            _ => MediaType::Unknown,
+        }
+    }
+}
+
+impl From<MediaType> for AVMediaType {
+    fn from(value: MediaType) -> Self {
+        match value {
+            MediaType::Unknown => AVMediaType_AVMEDIA_TYPE_UNKNOWN,
+            MediaType::Video => AVMediaType_AVMEDIA_TYPE_VIDEO,
+            MediaType::Audio => AVMediaType_AVMEDIA_TYPE_AUDIO,
+            MediaType::Data => AVMediaType_AVMEDIA_TYPE_DATA,
+            MediaType::Subtitle => AVMediaType_AVMEDIA_TYPE_SUBTITLE,
+            MediaType::Attachment => AVMediaType_AVMEDIA_TYPE_ATTACHMENT,
+        }
+    }
+}
+
+impl MediaType {
+    /// i.e. "video", "audio"
+    pub fn name(&self) -> &'static str {
+        unsafe {
+            let name = av_get_media_type_string((*self).into());
+            if name.is_null() {
+                "unknown"
+            } else {
+                CStr::from_ptr(name).to_str().unwrap_or("unknown")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn av_media_type_round_trip() {
+        let types = [
+            MediaType::Unknown,
+            MediaType::Video,
+            MediaType::Audio,
+            MediaType::Data,
+            MediaType::Subtitle,
+            MediaType::Attachment,
+        ];
+        for media_type in types.iter() {
+            assert_eq!(MediaType::from(AVMediaType::from(*media_type)), *media_type);
+        }
+        assert_eq!(
+            MediaType::from(AVMediaType_AVMEDIA_TYPE_NB),
+            MediaType::Unknown
+        );
+    }
+}
@@ -0,0 +1,13 @@
+pub mod dictionary;
+pub mod media_type;
+pub mod picture_type;
+pub mod pixel_format;
+pub mod rational;
+pub mod sample_format;
+
+pub use self::dictionary::{Dictionary, DictionaryFlags, DictionaryIter};
+pub use self::media_type::MediaType;
+pub use self::picture_type::PictureType;
+pub use self::pixel_format::PixelFormat;
+pub use self::rational::Rational;
+pub use self::sample_format::SampleFormat;
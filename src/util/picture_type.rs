@@ -0,0 +1,51 @@
+use crate::*;
+
+/// the AV_PICTURE_TYPE_* values
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PictureType {
+    None,
+    /// intra
+    I,
+    /// predicted
+    P,
+    /// bi-directionally predicted
+    B,
+    /// S(GMC)-VOP MPEG-4
+    S,
+    /// switching intra
+    Si,
+    /// switching predicted
+    Sp,
+    /// BI type
+    Bi,
+}
+
+impl From<AVPictureType> for PictureType {
+    fn from(value: AVPictureType) -> Self {
+        match value {
+            AVPictureType_AV_PICTURE_TYPE_I => PictureType::I,
+            AVPictureType_AV_PICTURE_TYPE_P => PictureType::P,
+            AVPictureType_AV_PICTURE_TYPE_B => PictureType::B,
+            AVPictureType_AV_PICTURE_TYPE_S => PictureType::S,
+            AVPictureType_AV_PICTURE_TYPE_SI => PictureType::Si,
+            AVPictureType_AV_PICTURE_TYPE_SP => PictureType::Sp,
+            AVPictureType_AV_PICTURE_TYPE_BI => PictureType::Bi,
+            _ => PictureType::None,
+        }
+    }
+}
+
+impl From<PictureType> for AVPictureType {
+    fn from(value: PictureType) -> Self {
+        match value {
+            PictureType::None => AVPictureType_AV_PICTURE_TYPE_NONE,
+            PictureType::I => AVPictureType_AV_PICTURE_TYPE_I,
+            PictureType::P => AVPictureType_AV_PICTURE_TYPE_P,
+            PictureType::B => AVPictureType_AV_PICTURE_TYPE_B,
+            PictureType::S => AVPictureType_AV_PICTURE_TYPE_S,
+            PictureType::Si => AVPictureType_AV_PICTURE_TYPE_SI,
+            PictureType::Sp => AVPictureType_AV_PICTURE_TYPE_SP,
+            PictureType::Bi => AVPictureType_AV_PICTURE_TYPE_BI,
+        }
+    }
+}
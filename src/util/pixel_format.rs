@@ -0,0 +1,152 @@
+use crate::*;
+use std::ffi::{CStr, CString};
+
+/// the AV_PIX_FMT_* values, formats without a variant are kept in Other
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PixelFormat {
+    None,
+    Yuv420p,
+    Yuyv422,
+    Rgb24,
+    Bgr24,
+    Yuv422p,
+    Yuv444p,
+    Gray8,
+    Yuvj420p,
+    Yuvj422p,
+    Yuvj444p,
+    Nv12,
+    Nv21,
+    Argb,
+    Rgba,
+    Abgr,
+    Bgra,
+    Yuv420p10le,
+    P010le,
+    /// any value without a variant of its own
+    Other(AVPixelFormat),
+}
+
+impl From<AVPixelFormat> for PixelFormat {
+    fn from(value: AVPixelFormat) -> Self {
+        match value {
+            AVPixelFormat_AV_PIX_FMT_NONE => PixelFormat::None,
+            AVPixelFormat_AV_PIX_FMT_YUV420P => PixelFormat::Yuv420p,
+            AVPixelFormat_AV_PIX_FMT_YUYV422 => PixelFormat::Yuyv422,
+            AVPixelFormat_AV_PIX_FMT_RGB24 => PixelFormat::Rgb24,
+            AVPixelFormat_AV_PIX_FMT_BGR24 => PixelFormat::Bgr24,
+            AVPixelFormat_AV_PIX_FMT_YUV422P => PixelFormat::Yuv422p,
+            AVPixelFormat_AV_PIX_FMT_YUV444P => PixelFormat::Yuv444p,
+            AVPixelFormat_AV_PIX_FMT_GRAY8 => PixelFormat::Gray8,
+            AVPixelFormat_AV_PIX_FMT_YUVJ420P => PixelFormat::Yuvj420p,
+            AVPixelFormat_AV_PIX_FMT_YUVJ422P => PixelFormat::Yuvj422p,
+            AVPixelFormat_AV_PIX_FMT_YUVJ444P => PixelFormat::Yuvj444p,
+            AVPixelFormat_AV_PIX_FMT_NV12 => PixelFormat::Nv12,
+            AVPixelFormat_AV_PIX_FMT_NV21 => PixelFormat::Nv21,
+            AVPixelFormat_AV_PIX_FMT_ARGB => PixelFormat::Argb,
+            AVPixelFormat_AV_PIX_FMT_RGBA => PixelFormat::Rgba,
+            AVPixelFormat_AV_PIX_FMT_ABGR => PixelFormat::Abgr,
+            AVPixelFormat_AV_PIX_FMT_BGRA => PixelFormat::Bgra,
+            AVPixelFormat_AV_PIX_FMT_YUV420P10LE => PixelFormat::Yuv420p10le,
+            AVPixelFormat_AV_PIX_FMT_P010LE => PixelFormat::P010le,
+            value => PixelFormat::Other(value),
+        }
+    }
+}
+
+impl From<PixelFormat> for AVPixelFormat {
+    fn from(value: PixelFormat) -> Self {
+        match value {
+            PixelFormat::None => AVPixelFormat_AV_PIX_FMT_NONE,
+            PixelFormat::Yuv420p => AVPixelFormat_AV_PIX_FMT_YUV420P,
+            PixelFormat::Yuyv422 => AVPixelFormat_AV_PIX_FMT_YUYV422,
+            PixelFormat::Rgb24 => AVPixelFormat_AV_PIX_FMT_RGB24,
+            PixelFormat::Bgr24 => AVPixelFormat_AV_PIX_FMT_BGR24,
+            PixelFormat::Yuv422p => AVPixelFormat_AV_PIX_FMT_YUV422P,
+            PixelFormat::Yuv444p => AVPixelFormat_AV_PIX_FMT_YUV444P,
+            PixelFormat::Gray8 => AVPixelFormat_AV_PIX_FMT_GRAY8,
+            PixelFormat::Yuvj420p => AVPixelFormat_AV_PIX_FMT_YUVJ420P,
+            PixelFormat::Yuvj422p => AVPixelFormat_AV_PIX_FMT_YUVJ422P,
+            PixelFormat::Yuvj444p => AVPixelFormat_AV_PIX_FMT_YUVJ444P,
+            PixelFormat::Nv12 => AVPixelFormat_AV_PIX_FMT_NV12,
+            PixelFormat::Nv21 => AVPixelFormat_AV_PIX_FMT_NV21,
+            PixelFormat::Argb => AVPixelFormat_AV_PIX_FMT_ARGB,
+            PixelFormat::Rgba => AVPixelFormat_AV_PIX_FMT_RGBA,
+            PixelFormat::Abgr => AVPixelFormat_AV_PIX_FMT_ABGR,
+            PixelFormat::Bgra => AVPixelFormat_AV_PIX_FMT_BGRA,
+            PixelFormat::Yuv420p10le => AVPixelFormat_AV_PIX_FMT_YUV420P10LE,
+            PixelFormat::P010le => AVPixelFormat_AV_PIX_FMT_P010LE,
+            PixelFormat::Other(value) => value,
+        }
+    }
+}
+
+impl PixelFormat {
+    /// i.e. "yuv420p", "rgba"
+    pub fn name(&self) -> Option<&'static str> {
+        unsafe {
+            let name = av_get_pix_fmt_name((*self).into());
+            if name.is_null() {
+                None
+            } else {
+                CStr::from_ptr(name).to_str().ok()
+            }
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        let c_name = CString::new(name).ok()?;
+        match PixelFormat::from(unsafe { av_get_pix_fmt(c_name.as_ptr()) }) {
+            PixelFormat::None => None,
+            pix_fmt => Some(pix_fmt),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn av_pixel_format_round_trip() {
+        let formats = [
+            PixelFormat::None,
+            PixelFormat::Yuv420p,
+            PixelFormat::Yuyv422,
+            PixelFormat::Rgb24,
+            PixelFormat::Bgr24,
+            PixelFormat::Yuv422p,
+            PixelFormat::Yuv444p,
+            PixelFormat::Gray8,
+            PixelFormat::Yuvj420p,
+            PixelFormat::Yuvj422p,
+            PixelFormat::Yuvj444p,
+            PixelFormat::Nv12,
+            PixelFormat::Nv21,
+            PixelFormat::Argb,
+            PixelFormat::Rgba,
+            PixelFormat::Abgr,
+            PixelFormat::Bgra,
+            PixelFormat::Yuv420p10le,
+            PixelFormat::P010le,
+        ];
+        for format in formats.iter() {
+            assert_eq!(PixelFormat::from(AVPixelFormat::from(*format)), *format);
+        }
+    }
+
+    #[test]
+    fn other_keeps_its_value() {
+        let value = AVPixelFormat_AV_PIX_FMT_NB - 1;
+        assert_eq!(PixelFormat::from(value), PixelFormat::Other(value));
+        assert_eq!(AVPixelFormat::from(PixelFormat::Other(value)), value);
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(PixelFormat::Yuv420p.name(), Some("yuv420p"));
+        assert_eq!(PixelFormat::None.name(), None);
+        assert_eq!(PixelFormat::from_name("rgba"), Some(PixelFormat::Rgba));
+        assert_eq!(PixelFormat::from_name("not a format"), None);
+    }
+}
@@ -0,0 +1,108 @@
+use crate::*;
+use libc::c_int;
+use std::fmt;
+
+/// a fraction such as a time base or a frame rate, mirrors AVRational
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Rational {
+    pub num: c_int,
+    pub den: c_int,
+}
+
+impl Rational {
+    pub const fn new(num: c_int, den: c_int) -> Self {
+        Rational { num, den }
+    }
+
+    /// 0/0 is used by libav for unknown values
+    pub fn is_valid(&self) -> bool {
+        self.num != 0 && self.den != 0
+    }
+
+    pub fn invert(&self) -> Self {
+        Rational::new(self.den, self.num)
+    }
+
+    pub fn as_f64(&self) -> f64 {
+        if self.den == 0 {
+            0.0
+        } else {
+            self.num as f64 / self.den as f64
+        }
+    }
+
+    /// convert ts from this time base to dst, rounding to nearest
+    pub fn rescale(&self, ts: i64, dst: Rational) -> i64 {
+        unsafe { av_rescale_q(ts, (*self).into(), dst.into()) }
+    }
+}
+
+impl From<AVRational> for Rational {
+    fn from(r: AVRational) -> Self {
+        Rational::new(r.num, r.den)
+    }
+}
+
+impl From<Rational> for AVRational {
+    fn from(r: Rational) -> Self {
+        AVRational {
+            num: r.num,
+            den: r.den,
+        }
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.num, self.den)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validity() {
+        assert!(Rational::new(1, 25).is_valid());
+        assert!(!Rational::new(0, 1).is_valid());
+        assert!(!Rational::new(1, 0).is_valid());
+        assert!(!Rational::default().is_valid());
+    }
+
+    #[test]
+    fn invert_and_as_f64() {
+        assert_eq!(Rational::new(1, 25).invert(), Rational::new(25, 1));
+        assert_eq!(
+            Rational::new(30000, 1001).invert(),
+            Rational::new(1001, 30000)
+        );
+        assert_eq!(Rational::new(1, 4).as_f64(), 0.25);
+        assert_eq!(Rational::new(1, 0).as_f64(), 0.0);
+    }
+
+    #[test]
+    fn rescale() {
+        let ms = Rational::new(1, 1000);
+        let mpeg = Rational::new(1, 90000);
+        assert_eq!(ms.rescale(1000, mpeg), 90000);
+        assert_eq!(mpeg.rescale(4500, ms), 50);
+        // rounds to nearest
+        assert_eq!(Rational::new(1, 3).rescale(1, ms), 333);
+        assert_eq!(Rational::new(2, 3).rescale(1, ms), 667);
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(Rational::new(1, 90000).to_string(), "1/90000");
+        assert_eq!(Rational::new(-1, 2).to_string(), "-1/2");
+    }
+
+    #[test]
+    fn av_rational_round_trip() {
+        let r = Rational::new(30000, 1001);
+        let av: AVRational = r.into();
+        assert_eq!((av.num, av.den), (30000, 1001));
+        assert_eq!(Rational::from(av), r);
+    }
+}
@@ -0,0 +1,137 @@
+use crate::*;
+use std::ffi::{CStr, CString};
+
+/// the AV_SAMPLE_FMT_* values, the *p variants are planar
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SampleFormat {
+    None,
+    U8,
+    S16,
+    S32,
+    Flt,
+    Dbl,
+    U8p,
+    S16p,
+    S32p,
+    Fltp,
+    Dblp,
+    S64,
+    S64p,
+    /// any value without a variant of its own
+    Other(AVSampleFormat),
+}
+
+impl From<AVSampleFormat> for SampleFormat {
+    fn from(value: AVSampleFormat) -> Self {
+        match value {
+            AVSampleFormat_AV_SAMPLE_FMT_NONE => SampleFormat::None,
+            AVSampleFormat_AV_SAMPLE_FMT_U8 => SampleFormat::U8,
+            AVSampleFormat_AV_SAMPLE_FMT_S16 => SampleFormat::S16,
+            AVSampleFormat_AV_SAMPLE_FMT_S32 => SampleFormat::S32,
+            AVSampleFormat_AV_SAMPLE_FMT_FLT => SampleFormat::Flt,
+            AVSampleFormat_AV_SAMPLE_FMT_DBL => SampleFormat::Dbl,
+            AVSampleFormat_AV_SAMPLE_FMT_U8P => SampleFormat::U8p,
+            AVSampleFormat_AV_SAMPLE_FMT_S16P => SampleFormat::S16p,
+            AVSampleFormat_AV_SAMPLE_FMT_S32P => SampleFormat::S32p,
+            AVSampleFormat_AV_SAMPLE_FMT_FLTP => SampleFormat::Fltp,
+            AVSampleFormat_AV_SAMPLE_FMT_DBLP => SampleFormat::Dblp,
+            AVSampleFormat_AV_SAMPLE_FMT_S64 => SampleFormat::S64,
+            AVSampleFormat_AV_SAMPLE_FMT_S64P => SampleFormat::S64p,
+            value => SampleFormat::Other(value),
+        }
+    }
+}
+
+impl From<SampleFormat> for AVSampleFormat {
+    fn from(value: SampleFormat) -> Self {
+        match value {
+            SampleFormat::None => AVSampleFormat_AV_SAMPLE_FMT_NONE,
+            SampleFormat::U8 => AVSampleFormat_AV_SAMPLE_FMT_U8,
+            SampleFormat::S16 => AVSampleFormat_AV_SAMPLE_FMT_S16,
+            SampleFormat::S32 => AVSampleFormat_AV_SAMPLE_FMT_S32,
+            SampleFormat::Flt => AVSampleFormat_AV_SAMPLE_FMT_FLT,
+            SampleFormat::Dbl => AVSampleFormat_AV_SAMPLE_FMT_DBL,
+            SampleFormat::U8p => AVSampleFormat_AV_SAMPLE_FMT_U8P,
+            SampleFormat::S16p => AVSampleFormat_AV_SAMPLE_FMT_S16P,
+            SampleFormat::S32p => AVSampleFormat_AV_SAMPLE_FMT_S32P,
+            SampleFormat::Fltp => AVSampleFormat_AV_SAMPLE_FMT_FLTP,
+            SampleFormat::Dblp => AVSampleFormat_AV_SAMPLE_FMT_DBLP,
+            SampleFormat::S64 => AVSampleFormat_AV_SAMPLE_FMT_S64,
+            SampleFormat::S64p => AVSampleFormat_AV_SAMPLE_FMT_S64P,
+            SampleFormat::Other(value) => value,
+        }
+    }
+}
+
+impl SampleFormat {
+    /// i.e. "s16", "fltp"
+    pub fn name(&self) -> Option<&'static str> {
+        unsafe {
+            let name = av_get_sample_fmt_name((*self).into());
+            if name.is_null() {
+                None
+            } else {
+                CStr::from_ptr(name).to_str().ok()
+            }
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        let c_name = CString::new(name).ok()?;
+        match SampleFormat::from(unsafe { av_get_sample_fmt(c_name.as_ptr()) }) {
+            SampleFormat::None => None,
+            sample_fmt => Some(sample_fmt),
+        }
+    }
+
+    pub fn is_planar(&self) -> bool {
+        unsafe { av_sample_fmt_is_planar((*self).into()) != 0 }
+    }
+
+    pub fn bytes_per_sample(&self) -> usize {
+        unsafe { av_get_bytes_per_sample((*self).into()).max(0) as usize }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn av_sample_format_round_trip() {
+        let formats = [
+            SampleFormat::None,
+            SampleFormat::U8,
+            SampleFormat::S16,
+            SampleFormat::S32,
+            SampleFormat::Flt,
+            SampleFormat::Dbl,
+            SampleFormat::U8p,
+            SampleFormat::S16p,
+            SampleFormat::S32p,
+            SampleFormat::Fltp,
+            SampleFormat::Dblp,
+            SampleFormat::S64,
+            SampleFormat::S64p,
+        ];
+        for format in formats.iter() {
+            assert_eq!(SampleFormat::from(AVSampleFormat::from(*format)), *format);
+        }
+    }
+
+    #[test]
+    fn other_keeps_its_value() {
+        let value = AVSampleFormat_AV_SAMPLE_FMT_NB;
+        assert_eq!(SampleFormat::from(value), SampleFormat::Other(value));
+        assert_eq!(AVSampleFormat::from(SampleFormat::Other(value)), value);
+    }
+
+    #[test]
+    fn properties() {
+        assert_eq!(SampleFormat::Fltp.name(), Some("fltp"));
+        assert_eq!(SampleFormat::from_name("s16"), Some(SampleFormat::S16));
+        assert!(SampleFormat::S16p.is_planar());
+        assert!(!SampleFormat::S16.is_planar());
+        assert_eq!(SampleFormat::S32.bytes_per_sample(), 4);
+    }
+}